@@ -0,0 +1,16 @@
+//!
+//! Generate the messages of `proto/report.proto` for the `protobuf` output
+//! format with a vendored `protoc`, so building doesn't need one installed.
+//!
+
+fn main() {
+    #[cfg(feature = "protobuf")]
+    {
+        println!("cargo:rerun-if-changed=proto/report.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("a vendored protoc");
+        prost_build::Config::new()
+            .protoc_executable(protoc)
+            .compile_protos(&["proto/report.proto"], &["proto"])
+            .expect("proto/report.proto compiles");
+    }
+}
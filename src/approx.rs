@@ -0,0 +1,109 @@
+//!
+//! Tolerance-based floating point comparisons for testing signal outputs.
+//! The assertions are for the tests of new signals, so nothing outside of
+//! tests calls them yet.
+//!
+#![cfg_attr(not(test), allow(dead_code))]
+
+///
+/// The default tolerance for comparing calculated signals.
+///
+pub const EPSILON: f64 = 1e-9;
+
+///
+/// Compare two floats with an absolute tolerance of `eps`. Infinities are only
+/// equal to the same infinity and `NaN` is never equal to anything, just like
+/// with `==`.
+///
+pub fn approx_eq(a: f64, b: f64, eps: f64) -> bool {
+    #[allow(clippy::float_cmp)]
+    let identical = a == b;
+    identical || (a - b).abs() <= eps
+}
+
+///
+/// Compare two series element-wise with `approx_eq`. Series of different
+/// lengths are never equal.
+///
+pub fn approx_eq_all(a: &[f64], b: &[f64], eps: f64) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| approx_eq(*a, *b, eps))
+}
+
+///
+/// Assert that `actual` is within `EPSILON` of `expected`.
+///
+pub fn assert_approx_eq(actual: f64, expected: f64) {
+    assert!(
+        approx_eq(actual, expected, EPSILON),
+        "{actual:?} != {expected:?}"
+    );
+}
+
+///
+/// Assert that two series are element-wise within `EPSILON` of each other.
+///
+pub fn assert_all_approx_eq(actual: &[f64], expected: &[f64]) {
+    assert!(
+        approx_eq_all(actual, expected, EPSILON),
+        "{actual:?} != {expected:?}"
+    );
+}
+
+///
+/// Assert that a signal is `Some` and approximately `expected`.
+///
+pub fn assert_some_approx_eq(actual: Option<f64>, expected: f64) {
+    assert_approx_eq(actual.expect("signal returned None"), expected);
+}
+
+///
+/// Assert that a pair-valued signal is `Some` and approximately `expected`.
+///
+pub fn assert_pair_approx_eq(actual: Option<(f64, f64)>, expected: (f64, f64)) {
+    let (a, b) = actual.expect("signal returned None");
+    assert!(
+        approx_eq(a, expected.0, EPSILON) && approx_eq(b, expected.1, EPSILON),
+        "{:?} != {:?}",
+        (a, b),
+        expected
+    );
+}
+
+///
+/// Assert that a series-valued signal is `Some` and approximately `expected`.
+///
+pub fn assert_series_approx_eq(actual: Option<Vec<f64>>, expected: &[f64]) {
+    assert_all_approx_eq(&actual.expect("signal returned None"), expected);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approx_eq() {
+        assert!(approx_eq(1.0, 1.0, 0.0));
+        assert!(approx_eq(0.1 + 0.2, 0.3, EPSILON));
+        assert!(approx_eq(1.0, 1.05, 0.1));
+        assert!(!approx_eq(1.0, 1.2, 0.1));
+        assert!(approx_eq(-0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_approx_eq_non_finite() {
+        assert!(!approx_eq(f64::NAN, f64::NAN, EPSILON));
+        assert!(!approx_eq(f64::NAN, 1.0, f64::INFINITY));
+        assert!(approx_eq(f64::INFINITY, f64::INFINITY, EPSILON));
+        assert!(approx_eq(f64::NEG_INFINITY, f64::NEG_INFINITY, EPSILON));
+        assert!(!approx_eq(f64::INFINITY, f64::NEG_INFINITY, EPSILON));
+        assert!(!approx_eq(f64::INFINITY, f64::MAX, EPSILON));
+    }
+
+    #[test]
+    fn test_approx_eq_all() {
+        assert!(approx_eq_all(&[], &[], EPSILON));
+        assert!(approx_eq_all(&[0.1 + 0.2, 1.0], &[0.3, 1.0], EPSILON));
+        assert!(!approx_eq_all(&[1.0], &[1.0, 1.0], EPSILON));
+        assert!(!approx_eq_all(&[1.0, f64::NAN], &[1.0, f64::NAN], EPSILON));
+    }
+}
@@ -0,0 +1,269 @@
+//!
+//! Running several reports with their own watchlists and periods in one go.
+//!
+
+use chrono::prelude::*;
+use clap::{AppSettings, ArgEnum, Parser};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::io::{self, Error, ErrorKind};
+
+use crate::output::{escape_influx_tag, Row, Style, TimestampFormat};
+use crate::report::SymbolReport;
+
+///
+/// The signals of a report a spec can pick, each with its own column.
+///
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    Change,
+    Min,
+    Max,
+    Sma,
+    External,
+}
+
+///
+/// A single line of a batch file, taking a subset of the command line options.
+///
+#[derive(Parser, Debug, PartialEq)]
+#[clap(setting = AppSettings::NoBinaryName)]
+pub struct BatchSpec {
+    /// Name labelling the report: a comment above it or a field of each row [default: the symbols]
+    #[clap(long)]
+    pub label: Option<String>,
+    #[clap(short, long)]
+    pub symbols: String,
+    #[clap(short, long, parse(try_from_str = crate::parse_date))]
+    pub from: DateTime<Utc>,
+    #[clap(short, long, parse(try_from_str = crate::parse_date))]
+    pub to: Option<DateTime<Utc>>,
+    /// The window of the report's simple moving average
+    #[clap(long, default_value = "30")]
+    pub sma_window: usize,
+    /// The comma-separated signals to report [default: all of them]
+    #[clap(long, arg_enum, use_delimiter = true)]
+    pub signals: Vec<Signal>,
+}
+
+impl BatchSpec {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.symbols)
+    }
+
+    ///
+    /// Whether the spec's report includes `signal`, which all of them do
+    /// unless some were picked.
+    ///
+    pub fn reports(&self, signal: Signal) -> bool {
+        self.signals.is_empty() || self.signals.contains(&signal)
+    }
+}
+
+///
+/// A report of a spec, with only the signals the spec picked and labelled
+/// with it in every format without comments. The moving average column is
+/// named after the spec's window.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRow<'a> {
+    pub spec: &'a BatchSpec,
+    pub report: SymbolReport,
+}
+
+impl BatchRow<'_> {
+    ///
+    /// The signal of a `SymbolReport` CSV column, if it's one.
+    ///
+    fn signal(column: &str) -> Option<Signal> {
+        match column {
+            "change %" => Some(Signal::Change),
+            "min" => Some(Signal::Min),
+            "max" => Some(Signal::Max),
+            "30d avg" => Some(Signal::Sma),
+            "external" => Some(Signal::External),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Keep the items of `values`, paired with the report's CSV columns, that
+    /// aren't a signal the spec left out.
+    ///
+    fn picked<T>(&self, values: Vec<T>) -> Vec<(String, T)> {
+        self.report
+            .csv_columns()
+            .into_iter()
+            .zip(values)
+            .filter(|(column, _)| Self::signal(column).is_none_or(|s| self.spec.reports(s)))
+            .collect()
+    }
+}
+
+impl Serialize for BatchRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json(&TimestampFormat::Rfc3339)
+            .serialize(serializer)
+    }
+}
+
+impl Row for BatchRow<'_> {
+    const CSV_HEADER: &'static str = SymbolReport::CSV_HEADER;
+
+    fn csv_fields(&self, style: &Style) -> Vec<String> {
+        self.picked(self.report.csv_fields(style))
+            .into_iter()
+            .map(|(_, field)| field)
+            .collect()
+    }
+
+    fn csv_columns(&self) -> Vec<String> {
+        self.picked(self.report.csv_columns())
+            .into_iter()
+            .map(|(column, _)| match Self::signal(&column) {
+                Some(Signal::Sma) => format!("{}d avg", self.spec.sma_window),
+                _ => column,
+            })
+            .collect()
+    }
+
+    fn to_json(&self, timestamps: &TimestampFormat) -> Value {
+        let mut json = self.report.to_json(timestamps);
+        if let Value::Object(fields) = &mut json {
+            for (signal, keys) in [
+                (Signal::Change, &["pct_change"][..]),
+                (Signal::Min, &["period_min"]),
+                (Signal::Max, &["period_max"]),
+                (Signal::Sma, &["sma", "sma_series"]),
+                (Signal::External, &["external"]),
+            ] {
+                if !self.spec.reports(signal) {
+                    for key in keys {
+                        fields.remove(*key);
+                    }
+                }
+            }
+            fields.insert("label".to_string(), self.spec.label().into());
+        }
+        json
+    }
+
+    ///
+    /// A `stock` record like the report's, tagged with the label as well.
+    ///
+    fn to_influx(&self) -> String {
+        let report = &self.report;
+        let mut fields = vec![format!("price={}", report.last_price)];
+        for (signal, key, value) in [
+            (Signal::Change, "change_pct", report.pct_change),
+            (Signal::Min, "min", report.period_min),
+            (Signal::Max, "max", report.period_max),
+            (Signal::Sma, "sma", report.sma),
+        ] {
+            if self.spec.reports(signal) {
+                fields.push(format!("{key}={value}"));
+            }
+        }
+        format!(
+            "stock,symbol={},label={} {} {}",
+            escape_influx_tag(&report.symbol),
+            escape_influx_tag(self.spec.label()),
+            fields.join(","),
+            report.last_close_time.timestamp_nanos()
+        )
+    }
+
+    fn change(&self) -> Option<f64> {
+        self.report.change()
+    }
+
+    fn series(&self) -> Vec<(&'static str, Vec<f64>)> {
+        self.report
+            .series()
+            .into_iter()
+            .filter(|(name, _)| match *name {
+                "sma" => self.spec.reports(Signal::Sma),
+                _ => self.spec.reports(Signal::External),
+            })
+            .collect()
+    }
+}
+
+///
+/// Parse a batch file with one spec of whitespace separated options per line,
+/// e.g. `--symbols AAPL,MSFT --from 2021-01-01 --sma-window 10`. Blank lines
+/// and lines starting with `#` are skipped.
+///
+/// # Errors
+///
+/// `InvalidInput` naming the first line that doesn't parse.
+///
+pub fn parse_batch(contents: &str) -> io::Result<Vec<BatchSpec>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(i, line)| {
+            BatchSpec::try_parse_from(line.split_whitespace()).map_err(|err| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid batch spec on line {}: {err}", i + 1),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch() {
+        let contents = "\
+# tech, the last quarter
+--label tech -s AAPL,MSFT --from 2021-09-01 --to 2021-12-01 --sma-window 10 --signals change,sma
+
+--symbols EURUSD=X,^GSPC -f 2021-01-01
+";
+        let specs = parse_batch(contents).unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                BatchSpec {
+                    label: Some("tech".to_string()),
+                    symbols: "AAPL,MSFT".to_string(),
+                    from: Utc.ymd(2021, 9, 1).and_hms(0, 0, 0),
+                    to: Some(Utc.ymd(2021, 12, 1).and_hms(0, 0, 0)),
+                    sma_window: 10,
+                    signals: vec![Signal::Change, Signal::Sma],
+                },
+                BatchSpec {
+                    label: None,
+                    symbols: "EURUSD=X,^GSPC".to_string(),
+                    from: Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+                    to: None,
+                    sma_window: 30,
+                    signals: vec![],
+                },
+            ]
+        );
+        assert_eq!(specs[0].label(), "tech");
+        assert_eq!(specs[1].label(), "EURUSD=X,^GSPC");
+        assert!(!specs[0].reports(Signal::Min));
+        assert!(specs[1].reports(Signal::Min));
+    }
+
+    #[test]
+    fn test_parse_batch_invalid() {
+        let err = parse_batch("-s AAPL -f 2021-01-01\n-s AAPL\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("line 2"));
+        assert!(parse_batch("-s AAPL -f yesterday").is_err());
+        assert!(parse_batch("").unwrap().is_empty());
+        assert!(parse_batch("-s AAPL -f 2021-01-01 --signals change,rsi").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//!
+//! An on-disk cache of the quotes of a provider, so repeated runs over the
+//! same period don't have to go to the network.
+//!
+
+use chrono::prelude::*;
+use std::{
+    fmt::Write,
+    fs,
+    io::{self, Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+use yahoo_finance_api as yahoo;
+
+use crate::provider::QuoteProvider;
+
+const HEADER: &str = "timestamp,open,high,low,close,adjclose,volume";
+
+///
+/// A provider answering from files in `dir` where possible and from `inner`
+/// otherwise, storing its answers for the next time. Requests are cached by
+/// symbol, the `interval` of the quotes and their exact period. Only periods
+/// ending before the current UTC day are cached, as their quotes can't
+/// change anymore, while those of today keep going to `inner`. Without a
+/// directory, every request goes to `inner`.
+///
+pub struct Cache<P> {
+    inner: P,
+    dir: Option<PathBuf>,
+    interval: &'static str,
+}
+
+impl<P: QuoteProvider> Cache<P> {
+    pub fn new(inner: P, dir: Option<PathBuf>, interval: &'static str) -> Self {
+        Cache {
+            inner,
+            dir,
+            interval,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+///
+/// The file caching the quotes of `symbol` at `interval` from `start` to
+/// `end`.
+///
+pub fn entry_path(
+    dir: &Path,
+    symbol: &str,
+    interval: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> PathBuf {
+    dir.join(format!(
+        "{symbol}_{interval}_{}_{}.csv",
+        start.format("%Y%m%dT%H%M%S"),
+        end.format("%Y%m%dT%H%M%S")
+    ))
+}
+
+fn to_csv(quotes: &[yahoo::Quote]) -> String {
+    let mut csv = format!("{HEADER}\n");
+    for q in quotes {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            q.timestamp, q.open, q.high, q.low, q.close, q.adjclose, q.volume
+        )
+        .expect("formatting into a String");
+    }
+    csv
+}
+
+fn from_csv(csv: &str) -> io::Result<Vec<yahoo::Quote>> {
+    let invalid = |line: &str| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid cache entry line '{line}'"),
+        )
+    };
+    let mut lines = csv.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "cache entry without header",
+        ));
+    }
+    lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let [timestamp, open, high, low, close, adjclose, volume] = fields[..] else {
+                return Err(invalid(line));
+            };
+            let price = |field: &str| field.parse::<f64>().map_err(|_| invalid(line));
+            Ok(yahoo::Quote {
+                timestamp: timestamp.parse().map_err(|_| invalid(line))?,
+                open: price(open)?,
+                high: price(high)?,
+                low: price(low)?,
+                close: price(close)?,
+                adjclose: price(adjclose)?,
+                volume: volume.parse().map_err(|_| invalid(line))?,
+            })
+        })
+        .collect()
+}
+
+///
+/// Write the entry to a temporary file first, so a concurrent reader never
+/// sees half of it.
+///
+fn store(path: &Path, quotes: &[yahoo::Quote]) -> io::Result<()> {
+    let partial = path.with_extension("csv.partial");
+    fs::write(&partial, to_csv(quotes))?;
+    fs::rename(partial, path)
+}
+
+impl<P: QuoteProvider> QuoteProvider for Cache<P> {
+    async fn quotes(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> io::Result<Vec<yahoo::Quote>> {
+        let today = Utc::now().date().and_hms(0, 0, 0);
+        let dir = match &self.dir {
+            Some(dir) if end < today => dir,
+            _ => return self.inner.quotes(symbol, start, end).await,
+        };
+        let path = entry_path(dir, symbol, self.interval, start, end);
+        match fs::read_to_string(&path) {
+            Ok(csv) => match from_csv(&csv) {
+                Ok(quotes) => return Ok(quotes),
+                Err(err) => eprintln!("ignoring {}: {err}", path.display()),
+            },
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => eprintln!("ignoring {}: {err}", path.display()),
+        }
+        let quotes = self.inner.quotes(symbol, start, end).await?;
+        if let Err(err) = store(&path, &quotes) {
+            eprintln!("can't cache {symbol} in {}: {err}", path.display());
+        }
+        Ok(quotes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::{provider::mock::MockProvider, series, sink};
+
+    #[tokio::test]
+    async fn test_Cache_quotes() {
+        let dir = sink::tests::temp_dir("cache-quotes");
+        let cache = Cache::new(MockProvider::daily(), Some(dir.clone()), "1d");
+        let (start, end) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 3).and_hms(12, 0, 0),
+        );
+        let fetched = cache.quotes("AAPL", start, end).await.unwrap();
+        assert_eq!(fetched.len(), 3);
+        assert!(entry_path(&dir, "AAPL", "1d", start, end).exists());
+        let cached = cache.quotes("AAPL", start, end).await.unwrap();
+        assert_eq!(cache.inner().call_count(), 1);
+        assert_eq!(to_csv(&cached), to_csv(&fetched));
+        // another period, symbol or interval is another entry
+        cache
+            .quotes("AAPL", start, end + chrono::Duration::hours(6))
+            .await
+            .unwrap();
+        cache.quotes("MSFT", start, end).await.unwrap();
+        let intraday = Cache::new(MockProvider::daily(), Some(dir.clone()), "5m");
+        intraday.quotes("AAPL", start, end).await.unwrap();
+        assert_eq!(cache.inner().call_count(), 3);
+        assert_eq!(intraday.inner().call_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_Cache_skips_today() {
+        let dir = sink::tests::temp_dir("cache-today");
+        let cache = Cache::new(MockProvider::daily(), Some(dir.clone()), "1d");
+        let end = Utc::now();
+        let start = end - chrono::Duration::days(3);
+        cache.quotes("AAPL", start, end).await.unwrap();
+        cache.quotes("AAPL", start, end).await.unwrap();
+        assert_eq!(cache.inner().call_count(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_Cache_without_dir() {
+        let cache = Cache::new(MockProvider::daily(), None, "1d");
+        let (start, end) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 3).and_hms(0, 0, 0),
+        );
+        cache.quotes("AAPL", start, end).await.unwrap();
+        cache.quotes("AAPL", start, end).await.unwrap();
+        assert_eq!(cache.inner().call_count(), 2);
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let quotes = vec![
+            series::tests::quote(1_609_459_200, 1.5),
+            yahoo::Quote {
+                volume: 0,
+                ..series::tests::quote(1_609_545_600, 2.0)
+            },
+        ];
+        assert_eq!(
+            to_csv(&from_csv(&to_csv(&quotes)).unwrap()),
+            to_csv(&quotes)
+        );
+        assert!(from_csv("1609459200,1,1,1,1,1,1\n").is_err());
+        assert!(from_csv(&format!("{HEADER}\n1609459200,1,1,1\n")).is_err());
+        assert!(from_csv(&format!("{HEADER}\n1609459200,1,1,1,x,1,1\n")).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//!
+//! Reducing long series to fewer points for plotting.
+//!
+
+///
+/// Select at most `threshold` of the points with the largest-triangle-three-
+/// buckets algorithm, which keeps the visual shape of the series: the first
+/// and last points are always kept and from each bucket in between the point
+/// forming the largest triangle with the previously selected point and the
+/// average of the next bucket.
+///
+/// # Returns
+///
+/// The indices of the selected points in ascending order.
+///
+#[allow(clippy::cast_precision_loss)]
+pub fn lttb(points: &[(f64, f64)], threshold: usize) -> Vec<usize> {
+    let n = points.len();
+    if threshold >= n {
+        return (0..n).collect();
+    }
+    match threshold {
+        0 => return vec![],
+        1 => return vec![0],
+        2 => return vec![0, n - 1],
+        _ => {}
+    }
+
+    // the first and last point are buckets of their own
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let bucket_start = |i: usize| ((i as f64 * every) as usize + 1).min(n - 1);
+    let mut selected = Vec::with_capacity(threshold);
+    selected.push(0);
+    let mut a = 0;
+    for i in 0..threshold - 2 {
+        let (next_start, next_end) = (
+            bucket_start(i + 1),
+            bucket_start(i + 2).max(bucket_start(i + 1) + 1).min(n),
+        );
+        let next = &points[next_start..next_end];
+        let avg_x = next.iter().map(|p| p.0).sum::<f64>() / next.len() as f64;
+        let avg_y = next.iter().map(|p| p.1).sum::<f64>() / next.len() as f64;
+
+        let (ax, ay) = points[a];
+        let mut max_area = -1.0;
+        let (start, end) = (bucket_start(i), bucket_start(i + 1));
+        for (j, (bx, by)) in points.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (by - ay) - (ax - bx) * (avg_y - ay)).abs();
+            if area > max_area {
+                max_area = area;
+                a = j;
+            }
+        }
+        selected.push(a);
+    }
+    selected.push(n - 1);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn sine(n: usize) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|i| {
+                let x = i as f64;
+                (x, (x / 100.0).sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lttb() {
+        let points = sine(10_000);
+        for threshold in [3, 100, 500, 9_999] {
+            let selected = lttb(&points, threshold);
+            assert!(selected.len() <= threshold);
+            assert_eq!(selected.first(), Some(&0));
+            assert_eq!(selected.last(), Some(&9_999));
+            assert!(selected.windows(2).all(|w| w[0] < w[1]));
+        }
+        // the extremes of the shape survive
+        let selected = lttb(&points, 500);
+        let max = selected
+            .iter()
+            .map(|i| points[*i].1)
+            .fold(f64::MIN, f64::max);
+        assert!(max > 0.999);
+    }
+
+    #[test]
+    fn test_lttb_edge_cases() {
+        let points = sine(5);
+        assert_eq!(lttb(&points, 5), vec![0, 1, 2, 3, 4]);
+        assert_eq!(lttb(&points, 10), vec![0, 1, 2, 3, 4]);
+        assert_eq!(lttb(&points, 2), vec![0, 4]);
+        assert_eq!(lttb(&points, 1), vec![0]);
+        assert!(lttb(&points, 0).is_empty());
+        assert!(lttb(&[], 3).is_empty());
+        assert_eq!(lttb(&points, 3).len(), 3);
+    }
+}
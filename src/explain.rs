@@ -0,0 +1,172 @@
+//!
+//! Human-readable descriptions of how each of the report's signals is
+//! calculated.
+//!
+
+use std::fmt::{Debug, Write};
+
+use crate::signals::{AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA};
+
+///
+/// The number of values shown from either end of a series.
+///
+const PREVIEW_LEN: usize = 3;
+
+///
+/// A description of a signal's calculation.
+///
+pub trait Explain {
+    ///
+    /// The signal's name.
+    ///
+    fn name(&self) -> &'static str;
+
+    ///
+    /// A short description of the formula.
+    ///
+    fn formula(&self) -> &'static str;
+
+    ///
+    /// The signal's parameters as name-value pairs.
+    ///
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![]
+    }
+}
+
+impl Explain for PriceDifference {
+    fn name(&self) -> &'static str {
+        "PriceDifference"
+    }
+
+    fn formula(&self) -> &'static str {
+        "(last - first, (last - first) / first)"
+    }
+}
+
+impl Explain for MinPrice {
+    fn name(&self) -> &'static str {
+        "MinPrice"
+    }
+
+    fn formula(&self) -> &'static str {
+        "min(closes)"
+    }
+}
+
+impl Explain for MaxPrice {
+    fn name(&self) -> &'static str {
+        "MaxPrice"
+    }
+
+    fn formula(&self) -> &'static str {
+        "max(closes)"
+    }
+}
+
+impl Explain for WindowedSMA {
+    fn name(&self) -> &'static str {
+        "WindowedSMA"
+    }
+
+    fn formula(&self) -> &'static str {
+        "sum(closes[i - window_size + 1..=i]) / window_size for each i"
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("window_size", self.window_size.to_string())]
+    }
+}
+
+///
+/// Abbreviate a series to its first and last few values.
+///
+fn preview(series: &[f64]) -> String {
+    let fmt = |values: &[f64]| {
+        values
+            .iter()
+            .map(|v| format!("{v:.2}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if series.len() <= 2 * PREVIEW_LEN {
+        format!("[{}]", fmt(series))
+    } else {
+        format!(
+            "[{}, ..., {}]",
+            fmt(&series[..PREVIEW_LEN]),
+            fmt(&series[series.len() - PREVIEW_LEN..])
+        )
+    }
+}
+
+///
+/// Describe the calculation of `signal` on `series` and its result.
+///
+pub fn explain<S>(signal: &S, series: &[f64]) -> String
+where
+    S: AsyncStockSignal + Explain,
+    S::SignalType: Debug,
+{
+    let parameters: Vec<_> = signal
+        .parameters()
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    let mut out = format!("{}\n", signal.name());
+    writeln!(out, "  formula: {}", signal.formula()).expect("formatting into a String");
+    if !parameters.is_empty() {
+        writeln!(out, "  parameters: {}", parameters.join(", ")).expect("formatting into a String");
+    }
+    writeln!(out, "  inputs ({}): {}", series.len(), preview(series))
+        .expect("formatting into a String");
+    writeln!(out, "  result: {:?}", signal.calculate(series)).expect("formatting into a String");
+    out
+}
+
+///
+/// Describe every signal of a symbol's report when calculated on `series`.
+///
+pub fn explain_report(symbol: &str, series: &[f64], sma_window: usize) -> String {
+    let mut out = format!("{symbol}\n");
+    out += &explain(&PriceDifference, series);
+    out += &explain(&MinPrice, series);
+    out += &explain(&MaxPrice, series);
+    out += &explain(
+        &WindowedSMA {
+            window_size: sma_window,
+        },
+        series,
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        let out = explain(&WindowedSMA { window_size: 3 }, &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            out,
+            "WindowedSMA\n  formula: sum(closes[i - window_size + 1..=i]) / window_size for each i\n  parameters: window_size=3\n  inputs (4): [1.00, 2.00, 3.00, 4.00]\n  result: Some([2.0, 3.0])\n"
+        );
+
+        let out = explain(&MinPrice, &[]);
+        assert!(!out.contains("parameters"));
+        assert!(out.ends_with("  result: None\n"));
+    }
+
+    #[test]
+    fn test_explain_report() {
+        let series: Vec<f64> = (1..=10).map(f64::from).collect();
+        let out = explain_report("AAPL", &series, 30);
+        assert!(out.starts_with("AAPL\nPriceDifference\n"));
+        assert!(out.contains("  inputs (10): [1.00, 2.00, 3.00, ..., 8.00, 9.00, 10.00]\n"));
+        assert!(out.contains("parameters: window_size=30"));
+        for name in ["PriceDifference", "MinPrice", "MaxPrice", "WindowedSMA"] {
+            assert!(out.contains(name), "{name}");
+        }
+    }
+}
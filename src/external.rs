@@ -0,0 +1,90 @@
+//!
+//! Signals calculated by external commands.
+//!
+
+use std::{
+    io::{self, Error, ErrorKind, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+///
+/// Run `command` with `sh -c`, write the closes to its stdin one per line and
+/// parse the first non-empty line of its output as whitespace-separated
+/// floats.
+///
+/// # Errors
+///
+/// If the command can't be started, exits unsuccessfully or prints anything
+/// but floats (`InvalidData`).
+///
+pub fn run_external(command: &str, closes: &[f64]) -> io::Result<Vec<f64>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    let input: String = closes.iter().map(|c| c.to_string() + "\n").collect();
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // write from another thread so a command that doesn't read all of its
+    // input can't block on a full stdout pipe
+    let writer = thread::spawn(move || {
+        // a command may exit without reading its input
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(Error::other(format!("'{command}' {}", output.status)));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("'{command}' didn't print a line of numbers"),
+        )
+    };
+    let line = stdout
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .ok_or_else(invalid)?;
+    line.split_whitespace()
+        .map(|value| value.parse().map_err(|_| invalid()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_external() {
+        let closes = [1.5, 2.0, 3.25];
+        assert_eq!(
+            run_external("awk '{ s += $1 } END { print s }'", &closes).unwrap(),
+            vec![6.75]
+        );
+        assert_eq!(run_external("tail -n 1", &closes).unwrap(), vec![3.25]);
+        assert_eq!(
+            run_external("echo; echo 1 -2.5 3e2", &closes).unwrap(),
+            vec![1.0, -2.5, 300.0]
+        );
+    }
+
+    #[test]
+    fn test_run_external_failures() {
+        let closes = [1.0, 2.0];
+        let err = run_external("exit 3", &closes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert!(err.to_string().contains("exit status: 3"));
+
+        for command in ["echo not a number", "true"] {
+            let err = run_external(command, &closes).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//!
+//! A liveness probe reporting whether ticks keep completing.
+//!
+
+use serde_json::json;
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+///
+/// When the last report was written. Until the first one, the time the
+/// process started counts as the last report so it has time to finish.
+///
+pub struct Health {
+    last_success: Mutex<Instant>,
+    stale_after: Duration,
+}
+
+impl Health {
+    pub fn new(stale_after: Duration) -> Self {
+        Health {
+            last_success: Mutex::new(Instant::now()),
+            stale_after,
+        }
+    }
+
+    ///
+    /// Record a report written at `now`.
+    ///
+    pub fn succeed(&self, now: Instant) {
+        *self.last_success.lock().expect("health is never poisoned") = now;
+    }
+
+    ///
+    /// The status code and JSON body of the probe at `now`: 200 if the last
+    /// report was written within `stale_after`, 503 otherwise.
+    ///
+    pub fn check(&self, now: Instant) -> (u16, String) {
+        let since = now.saturating_duration_since(
+            *self.last_success.lock().expect("health is never poisoned"),
+        );
+        let (status, text) = if since <= self.stale_after {
+            (200, "ok")
+        } else {
+            (503, "stale")
+        };
+        let body = json!({
+            "status": text,
+            "seconds_since_last_report": since.as_secs(),
+        });
+        (status, body.to_string())
+    }
+}
+
+///
+/// The status line, headers and body answering a request for `path`. Only
+/// `/healthz` exists.
+///
+pub fn respond(health: &Health, path: &str, now: Instant) -> String {
+    let (status, body) = if path == "/healthz" {
+        health.check(now)
+    } else {
+        (404, json!({ "status": "not found" }).to_string())
+    };
+    let reason = match status {
+        200 => "OK",
+        503 => "Service Unavailable",
+        _ => "Not Found",
+    };
+    format!(
+        "HTTP/1.1 {status} {reason}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+///
+/// How long a client gets to send its request line before the connection is
+/// dropped.
+///
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+///
+/// Answer the probe on `stream`.
+///
+async fn answer(mut stream: TcpStream, health: &Health) -> io::Result<()> {
+    // the request line is all that matters and fits the first read
+    let mut request = [0; 1024];
+    let read = tokio::time::timeout(READ_TIMEOUT, stream.read(&mut request))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no request received"))??;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let response = respond(health, path, Instant::now());
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+///
+/// Answer probes on `listener` forever, each connection in its own task so a
+/// slow client doesn't hold up the others. Failing connections are only
+/// logged.
+///
+pub async fn serve(listener: TcpListener, health: Arc<Health>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                eprintln!("health check failed: {err}");
+                continue;
+            }
+        };
+        let health = Arc::clone(&health);
+        tokio::spawn(async move {
+            if let Err(err) = answer(stream, &health).await {
+                eprintln!("health check failed: {err}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+
+    #[test]
+    fn test_Health_check() {
+        let health = Health::new(Duration::from_secs(45));
+        let start = Instant::now();
+        health.succeed(start);
+        assert_eq!(
+            health.check(start + Duration::from_secs(30)),
+            (
+                200,
+                r#"{"seconds_since_last_report":30,"status":"ok"}"#.to_string()
+            )
+        );
+        // ticks stalled for longer than allowed
+        let (status, body) = health.check(start + Duration::from_secs(61));
+        assert_eq!(status, 503);
+        assert!(body.contains(r#""seconds_since_last_report":61"#));
+        assert!(
+            respond(&health, "/healthz", start + Duration::from_secs(61))
+                .starts_with("HTTP/1.1 503 Service Unavailable\r\n")
+        );
+        // a new report makes it healthy again
+        health.succeed(start + Duration::from_secs(90));
+        assert!(
+            respond(&health, "/healthz", start + Duration::from_secs(91))
+                .starts_with("HTTP/1.1 200 OK\r\n")
+        );
+        assert!(respond(&health, "/metrics", start).starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(
+            listener,
+            Arc::new(Health::new(Duration::from_secs(45))),
+        ));
+        // a client that never sends anything doesn't block the next one
+        let _silent = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#""status":"ok"}"#));
+    }
+}
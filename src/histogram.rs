@@ -0,0 +1,118 @@
+//!
+//! Distribution summaries of daily returns.
+//!
+
+use serde_json::json;
+use std::fmt::Write;
+
+///
+/// The widest bar of an ASCII histogram in characters.
+///
+const MAX_BAR_WIDTH: usize = 40;
+
+///
+/// Bucket returns into `bins` equally wide bins between the smallest and the
+/// largest return. If all returns are equal there is only a single bin.
+///
+/// # Returns
+///
+/// The lower edge and number of returns of each bin, or nothing for empty
+/// returns or zero bins.
+///
+pub fn return_histogram(returns: &[f64], bins: usize) -> Vec<(f64, usize)> {
+    if returns.is_empty() || bins == 0 {
+        return vec![];
+    }
+    let min = returns.iter().fold(f64::MAX, |acc, r| acc.min(*r));
+    let max = returns.iter().fold(f64::MIN, |acc, r| acc.max(*r));
+    if max <= min {
+        return vec![(min, returns.len())];
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let width = (max - min) / bins as f64;
+    let mut histogram: Vec<_> = (0..bins)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let lower = min + width * i as f64;
+            (lower, 0)
+        })
+        .collect();
+    for r in returns {
+        // the maximum belongs to the last bin
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let i = (((r - min) / width) as usize).min(bins - 1);
+        histogram[i].1 += 1;
+    }
+    histogram
+}
+
+///
+/// Render a histogram as an ASCII bar chart with one line per bin.
+///
+pub fn render_histogram(symbol: &str, histogram: &[(f64, usize)]) -> String {
+    let mut out = format!("{symbol} daily returns\n");
+    let max_count = histogram.iter().map(|(_, c)| *c).max().unwrap_or(0);
+    for (lower, count) in histogram {
+        let width = (count * MAX_BAR_WIDTH).checked_div(max_count).unwrap_or(0);
+        writeln!(
+            out,
+            "{:>8.2}% |{:<width$} {count}",
+            lower * 100.0,
+            "#".repeat(width),
+            width = MAX_BAR_WIDTH
+        )
+        .expect("formatting into a String");
+    }
+    out
+}
+
+///
+/// Render a histogram as a single line JSON object.
+///
+pub fn render_histogram_json(symbol: &str, histogram: &[(f64, usize)]) -> String {
+    let bins: Vec<_> = histogram
+        .iter()
+        .map(|(lower, count)| json!({ "lower": lower, "count": count }))
+        .collect();
+    json!({ "symbol": symbol, "histogram": bins }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approx::assert_all_approx_eq;
+
+    #[test]
+    fn test_return_histogram() {
+        let returns = [-0.02, -0.01, 0.0, 0.0, 0.01, 0.015, 0.02];
+        let histogram = return_histogram(&returns, 4);
+        let counts: Vec<_> = histogram.iter().map(|(_, c)| *c).collect();
+        assert_eq!(counts, vec![1, 1, 2, 3]);
+        assert_eq!(counts.iter().sum::<usize>(), returns.len());
+        let edges: Vec<_> = histogram.iter().map(|(l, _)| *l).collect();
+        assert_all_approx_eq(&edges, &[-0.02, -0.01, 0.0, 0.01]);
+    }
+
+    #[test]
+    fn test_return_histogram_edge_cases() {
+        assert_eq!(return_histogram(&[0.01, 0.01, 0.01], 5), vec![(0.01, 3)]);
+        assert!(return_histogram(&[], 5).is_empty());
+        assert!(return_histogram(&[0.01, 0.02], 0).is_empty());
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let out = render_histogram("AAPL", &[(-0.01, 1), (0.0, 2)]);
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], "AAPL daily returns");
+        assert!(lines[1].starts_with("   -1.00% |"));
+        assert!(lines[1].ends_with(" 1"));
+        assert_eq!(lines[2].matches('#').count(), MAX_BAR_WIDTH);
+
+        assert_eq!(
+            render_histogram_json("AAPL", &[(0.0, 2)]),
+            r#"{"histogram":[{"count":2,"lower":0.0}],"symbol":"AAPL"}"#
+        );
+    }
+}
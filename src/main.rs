@@ -2,13 +2,89 @@
 
 use chrono::prelude::*;
 use clap::Parser;
+use serde::Serialize;
 use std::{
+    collections::HashMap,
     io::{self, Error, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
     time::Duration,
 };
-use tokio::time::MissedTickBehavior;
-use yahoo_finance_api as yahoo;
+use tokio::time::{Instant, MissedTickBehavior};
 
+mod approx;
+mod batch;
+mod cache;
+mod downsample;
+mod explain;
+mod external;
+mod health;
+mod histogram;
+mod market;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod output;
+mod progress;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod provider;
+mod report;
+mod rng;
+mod series;
+mod sink;
+mod sqlite;
+mod stale;
+mod ticks;
+mod watchlist;
+mod webhook;
+
+mod ohlc;
+mod signals;
+
+use batch::{BatchRow, BatchSpec};
+use cache::Cache;
+use health::Health;
+use ohlc::{AsyncBarSignal, AverageVolume};
+use output::{ColorChoice, OutputFormat, QuoteStyle, Row, Style, TimestampFormat};
+use progress::Progress;
+use provider::{Jitter, QuoteProvider, Retry, RetryBudget};
+use report::{diff_reports, Alerts, AsOfClose, ReportDiff, SeriesPoint, SymbolError, SymbolReport};
+use rng::Rng;
+use series::Series;
+use signals::{AsyncStockSignal, MaxPrice, MinPrice, PriceDifference, WindowedSMA};
+use sink::{Rotation, Sink};
+use sqlite::ReportDb;
+use stale::{LatestQuotes, StaleTicks};
+use ticks::TickPool;
+use watchlist::Watchlist;
+use webhook::Webhook;
+
+///
+/// The window of the report's simple moving average.
+///
+const SMA_WINDOW: usize = 30;
+
+///
+/// The delay before retrying to fetch a symbol.
+///
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+///
+/// The granularity of quotes for reports since the market opened.
+///
+const INTRADAY_INTERVAL: &str = "5m";
+
+///
+/// The granularity of quotes for all other reports.
+///
+const DAILY_INTERVAL: &str = "1d";
+
+///
+/// The least delay before retrying to fetch a symbol after being rate limited.
+///
+const RATE_LIMIT_DELAY: Duration = Duration::from_secs(10);
+
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser)]
 #[clap(
     version = "1.0",
@@ -18,273 +94,2308 @@ use yahoo_finance_api as yahoo;
 struct Opts {
     #[clap(short, long, default_value = "AAPL,MSFT,UBER,GOOG")]
     symbols: String,
+    /// Read the symbols from this file instead, re-reading it before every report
+    #[clap(long, conflicts_with = "symbols")]
+    symbols_file: Option<PathBuf>,
+    /// Start of the period as RFC3339, `YYYY-MM-DD` or Unix epoch seconds
+    #[clap(short, long, required_unless_present_any = &["as-of", "batch", "stdin", "since-open"])]
+    from: Option<String>,
+    /// End of the period in the same formats as `from` [default: now]
+    #[clap(short, long)]
+    to: Option<String>,
+    /// Start the period at the last open of US markets (09:30 in New York) and use intraday quotes
+    #[clap(long, conflicts_with_all = &["from", "as-of", "batch", "stdin"])]
+    since_open: bool,
+    /// Start of a second period to compare the report against
+    #[clap(long, requires = "compare-to")]
+    compare_from: Option<String>,
+    /// End of the second period to compare the report against
+    #[clap(long, requires = "compare-from")]
+    compare_to: Option<String>,
+    /// Only print each symbol's close on this date (or the last trading day before it)
+    #[clap(long, conflicts_with_all = &["from", "to", "compare-from"])]
+    as_of: Option<String>,
+    /// Run each report specified in this file once, one spec of options per line
+    #[clap(long, conflicts_with_all = &["from", "to", "compare-from", "as-of"])]
+    batch: Option<PathBuf>,
+    /// Report on newline-separated closes read from stdin instead of fetching any
+    #[clap(long, conflicts_with_all = &["from", "to", "compare-from", "as-of", "batch"])]
+    stdin: bool,
+    /// The symbol to label the closes read from stdin with
+    #[clap(long, default_value = "STDIN")]
+    stdin_symbol: String,
+    /// Only print symbols whose change moved by more than this many percentage points since the last tick
+    #[clap(long, conflicts_with = "compare-from")]
+    alert_threshold: Option<f64>,
+    /// The format to print reports in
+    #[clap(long, arg_enum, default_value = "csv")]
+    output_format: OutputFormat,
+    /// Print each tick as a single JSON array instead of one object per line
+    #[clap(long, conflicts_with = "summary")]
+    json_array: bool,
+    /// The field delimiter of CSV output
+    #[clap(long, default_value = ",")]
+    delimiter: char,
+    /// When to quote the fields of CSV and TSV output
+    #[clap(long, arg_enum, default_value = "necessary")]
+    quote_style: QuoteStyle,
+    /// Don't print the header line of CSV and TSV output
+    #[clap(short, long)]
+    quiet: bool,
+    /// Precede the header line of CSV and TSV ticks with `#` comments describing the run
+    #[clap(long, conflicts_with_all = &["quiet", "batch", "stdin"])]
+    header_comments: bool,
+    /// Start periods at most this many days before their end
+    #[clap(long)]
+    max_history_days: Option<u32>,
+    /// Fetch long periods in chunks of this many days
+    #[clap(long, alias = "chunk-dates")]
+    chunk_days: Option<u32>,
+    /// Print how each signal was calculated and from which inputs to stderr
+    #[clap(long)]
+    explain: bool,
+    /// Show how many symbols have been fetched on stderr
+    #[clap(long)]
+    progress: bool,
+    /// Include the full moving average series in structured formats
+    #[clap(long)]
+    series: bool,
+    /// Calculate the signals over only the last this many closes of the period
+    #[clap(long)]
+    tail: Option<usize>,
+    /// Drop the first values of emitted series while their signals warm up (single values use the full period)
+    #[clap(long, default_value = "0")]
+    warmup: usize,
+    /// Downsample emitted series to at most this many points
+    #[clap(long, requires = "series")]
+    max_points: Option<usize>,
+    /// Use the raw closes instead of those adjusted for splits and dividends
+    #[clap(long, alias = "no-adjust", conflicts_with = "stdin")]
+    raw_close: bool,
+    /// Collapse runs of identical consecutive closes, e.g. repeated stale bars
+    #[clap(long)]
+    dedup_closes: bool,
+    /// Retry fetching a symbol this many times, none without it
+    #[clap(long)]
+    retries: Option<usize>,
+    /// Cap the number of retries of all symbols of a tick
+    #[clap(long, requires = "retries")]
+    retry_budget: Option<usize>,
+    /// Extend each retry delay by a random part of up to this fraction of it
+    #[clap(long, requires = "retries")]
+    retry_jitter: Option<f64>,
+    /// Seed the randomness of retry delays to reproduce a run [default: random]
+    #[clap(long, requires = "retry-jitter")]
+    random_seed: Option<u64>,
+    /// Skip symbols trading fewer shares per day on average over the period
+    #[clap(long)]
+    min_avg_volume: Option<u64>,
+    /// Pipe each symbol's closes to this shell command and add the numbers it prints as a column
+    #[clap(long)]
+    external_signal: Option<String>,
+    /// Warm up each symbol's moving average with the older closes in the file `<SYMBOL>` in this directory, one per line
+    #[clap(long)]
+    seed: Option<PathBuf>,
+    /// Write the quotes of each symbol as fetched to `<SYMBOL>.csv` in this directory
+    #[clap(long)]
+    dump_raw_quotes: Option<PathBuf>,
+    /// Keep the fetched quotes of periods ending before today in this directory and reuse them
+    #[clap(long)]
+    cache_dir: Option<PathBuf>,
+    /// Only fill the cache with the quotes of every symbol over the period, then exit without reporting
+    #[clap(long, requires = "cache-dir", conflicts_with_all = &["as-of", "batch", "stdin"])]
+    prefetch: bool,
+    /// Print a histogram of each symbol's daily returns with this many bins to stderr
+    #[clap(long)]
+    histogram: Option<usize>,
+    /// Color gains and losses in the table format
+    #[clap(long, arg_enum, default_value = "auto")]
+    color: ColorChoice,
+    /// Decimal places of numbers in human-readable formats
+    #[clap(long, default_value = "2")]
+    precision: usize,
+    /// How to print timestamps: `rfc3339`, `date`, `epoch` or a strftime string like `%Y-%m-%d %H:%M`
+    #[clap(long, default_value = "rfc3339")]
+    timestamp_format: TimestampFormat,
+    /// Print a line aggregating the whole watchlist after each report
+    #[clap(long)]
+    summary: bool,
+    /// POST each tick's rows as JSON to this URL
+    #[clap(long)]
+    webhook: Option<String>,
+    /// Seconds until a webhook request is abandoned
+    #[clap(long, default_value = "10")]
+    webhook_timeout: u64,
+    /// Insert each tick's reports into the `reports` table of this `SQLite` database, creating both if necessary
+    #[clap(long, conflicts_with_all = &["as-of", "stdin", "prefetch"])]
+    sqlite: Option<PathBuf>,
+    /// Append the output to this file instead of printing it
     #[clap(short, long)]
-    from: String,
+    output: Option<PathBuf>,
+    /// Switch to a new, dated output file
+    #[clap(long, arg_enum, requires = "output")]
+    rotate: Option<Rotation>,
+    /// Print the output as well as writing it to the output file
+    #[clap(long, requires = "output")]
+    tee: bool,
+    /// Compress the JSON lines written to the output file with gzip
+    #[clap(long, requires = "output", conflicts_with = "rotate")]
+    gzip: bool,
+    /// Seconds between two reports
+    #[clap(long, default_value = "30")]
+    interval: u64,
+    /// Upper bound in seconds for the interval after repeated failures
+    #[clap(long, default_value = "300")]
+    max_interval: u64,
+    /// Serve a liveness probe at `/healthz` on this address, e.g. `127.0.0.1:8080`
+    #[clap(long)]
+    health_addr: Option<String>,
+    /// Seconds without a written report after which the probe fails
+    #[clap(long, default_value = "600")]
+    health_stale_after: u64,
+    /// Exit with an error after this many reports in a row without a newer quote for any symbol
+    #[clap(long)]
+    exit_on_stale: Option<usize>,
+    /// Start the next report on time even if up to this many are still running
+    #[clap(long, conflicts_with = "alert-threshold")]
+    parallel_ticks: Option<usize>,
 }
 
 ///
-/// A trait to provide a common interface for all signal calculations.
+/// Settings for processing each symbol's data, shared by all tasks of a tick.
 ///
-trait AsyncStockSignal {
-    ///
-    /// The signal's data type.
-    ///
-    type SignalType;
-
-    ///
-    /// Calculate the signal on the provided series.
-    ///
-    /// # Returns
-    ///
-    /// The signal (using the provided type) or `None` on error/invalid data.
-    ///
-    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType>;
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone)]
+struct ReportSettings {
+    format: OutputFormat,
+    style: Style,
+    quiet: bool,
+    summary: bool,
+    histogram_bins: Option<usize>,
+    explain: bool,
+    progress: bool,
+    series: bool,
+    tail: Option<usize>,
+    warmup: usize,
+    max_points: Option<usize>,
+    sma_window: usize,
+    min_avg_volume: Option<u64>,
+    retries: usize,
+    retry_budget: Option<usize>,
+    jitter: Option<Jitter>,
+    raw_close: bool,
+    dedup_closes: bool,
+    external_signal: Option<Arc<str>>,
+    seed: Option<Arc<HashMap<String, Vec<f64>>>>,
+    dump_raw_quotes: Option<Arc<Path>>,
+    latest: Option<Arc<LatestQuotes>>,
+    chunk: Option<chrono::Duration>,
+    sqlite: Option<Arc<Mutex<ReportDb>>>,
 }
 
-struct PriceDifference;
-struct MinPrice;
-struct MaxPrice;
-struct WindowedSMA {
-    window_size: usize,
+///
+/// Tracks consecutive failures of the report loop and derives the delay until
+/// the next report from it, doubling the base interval per failure up to a cap.
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Backoff {
+    base: Duration,
+    cap: Duration,
+    failures: u32,
 }
 
-impl AsyncStockSignal for PriceDifference {
-    type SignalType = (f64, f64);
+impl Backoff {
+    fn new(base: Duration, cap: Duration) -> Self {
+        Backoff {
+            base,
+            cap: cap.max(base),
+            failures: 0,
+        }
+    }
 
     ///
-    /// Calculates the absolute and relative difference between the beginning
-    /// and ending of an f64 series. The relative difference is relative to the
-    /// beginning.
+    /// The delay until the next report for the current number of failures.
     ///
-    /// # Returns
+    fn delay(&self) -> Duration {
+        // cap the exponent to avoid an overflow, the cap wins long before that
+        let factor = 2_u32.saturating_pow(self.failures.min(31));
+        self.base.saturating_mul(factor).min(self.cap)
+    }
+
     ///
-    /// A tuple `(absolute, relative)` difference.
+    /// Record a fully successful report and return the next delay.
     ///
-    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
-        if series.is_empty() {
-            return None;
-        }
-        // unwrap is safe here even if first == last
-        let (first, last) = (series.first().unwrap(), series.last().unwrap());
-        let abs_diff = last - first;
-        let first = if *first == 0.0 { 1.0 } else { *first };
-        let rel_diff = abs_diff / first;
-        Some((abs_diff, rel_diff))
+    fn succeed(&mut self) -> Duration {
+        self.failures = 0;
+        self.delay()
     }
-}
-
-impl AsyncStockSignal for MinPrice {
-    type SignalType = f64;
 
     ///
-    /// Find the minimum in a series of f64
+    /// Record a failed report and return the next delay.
     ///
-    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
-        if series.is_empty() {
-            None
-        } else {
-            Some(series.iter().fold(f64::MAX, |acc, q| acc.min(*q)))
-        }
+    fn fail(&mut self) -> Duration {
+        self.failures = self.failures.saturating_add(1);
+        self.delay()
     }
 }
 
-impl AsyncStockSignal for MaxPrice {
-    type SignalType = f64;
+///
+/// Split a comma-separated list of symbols, trimming and uppercasing each.
+///
+/// Supported symbol shapes are those Yahoo uses:
+///
+/// - plain tickers: `AAPL`
+/// - exchange suffixes: `BMW.DE`
+/// - share classes and crypto pairs: `BRK-B`, `BTC-USD`
+/// - forex pairs and futures: `EURUSD=X`, `GC=F`
+/// - indices: `^GSPC`
+///
+/// # Errors
+///
+/// An `InvalidInput` error for empty symbols, symbols containing whitespace or
+/// other characters, or a malformed `=` suffix.
+///
+fn parse_symbols(symbols: &str) -> io::Result<Vec<String>> {
+    symbols
+        .split(',')
+        .map(|symbol| {
+            let symbol = symbol.trim().to_uppercase();
+            let invalid = |reason: &str| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid symbol '{symbol}': {reason}"),
+                )
+            };
+            if symbol.is_empty() {
+                return Err(invalid("empty"));
+            }
+            let (base, suffix) = match symbol.split_once('=') {
+                Some((base, suffix)) => (base, Some(suffix)),
+                None => (symbol.as_str(), None),
+            };
+            let base = base.strip_prefix('^').unwrap_or(base);
+            if base.is_empty()
+                || !base
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+            {
+                return Err(invalid("only letters, digits, '.' and '-' are allowed"));
+            }
+            if let Some(suffix) = suffix {
+                if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(invalid("'=' must be followed by a suffix like 'X'"));
+                }
+            }
+            Ok(symbol)
+        })
+        .collect()
+}
 
-    ///
-    /// Find the maximum in a series of f64
-    ///
-    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
-        if series.is_empty() {
-            None
-        } else {
-            Some(series.iter().fold(f64::MIN, |acc, q| acc.max(*q)))
-        }
+///
+/// Parse a point in time given as RFC3339 (`2021-11-01T00:00:00Z`), a date
+/// (`2021-11-01`, midnight UTC) or Unix epoch seconds (`1635724800`, any input
+/// consisting only of digits).
+///
+/// # Errors
+///
+/// An `InvalidInput` error if the input matches none of these formats.
+///
+fn parse_date(input: &str) -> io::Result<DateTime<Utc>> {
+    let input = input.trim();
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid date '{input}': use RFC3339, YYYY-MM-DD or epoch seconds"),
+        )
+    };
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+        let secs: i64 = input.parse().map_err(|_| invalid())?;
+        return Utc.timestamp_opt(secs, 0).single().ok_or_else(invalid);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(Utc.from_utc_datetime(&date.and_hms(0, 0, 0)));
     }
+    input.parse().map_err(|_| invalid())
 }
 
-impl AsyncStockSignal for WindowedSMA {
-    type SignalType = Vec<f64>;
-
-    ///
-    /// Window function to create a simple moving average
-    ///
-    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
-        if !series.is_empty() && self.window_size > 1 {
-            #[allow(clippy::cast_precision_loss)]
-            Some(
-                series
-                    .windows(self.window_size)
-                    .map(|w| w.iter().sum::<f64>() / w.len() as f64)
-                    .collect(),
-            )
-        } else {
-            None
+///
+/// Move `from` forward to at most `max_days` days before `to`, logging when it
+/// does, so a runaway period can't exhaust memory.
+///
+fn clamp_from(from: DateTime<Utc>, to: DateTime<Utc>, max_days: Option<u32>) -> DateTime<Utc> {
+    match max_days {
+        Some(days) if from < to - chrono::Duration::days(days.into()) => {
+            let clamped = to - chrono::Duration::days(days.into());
+            eprintln!(
+                "clamping the start of the period from {} to {} ({days} days of history at most)",
+                from.to_rfc3339(),
+                clamped.to_rfc3339()
+            );
+            clamped
         }
+        _ => from,
     }
 }
 
 ///
-/// Retrieve data from a data source and extract the closing prices. Errors
-/// during download are mapped onto `io::Errors` as `InvalidData`.
+/// Look up each symbol's close as of `date`, raw or adjusted as `raw_close`
+/// says. Symbols without any trading in the days before are skipped.
 ///
-async fn fetch_closing_data(
-    symbol: &str,
-    beginning: &DateTime<Utc>,
-    end: &DateTime<Utc>,
-) -> std::io::Result<Vec<f64>> {
-    let provider = yahoo::YahooConnector::new();
+async fn run_as_of<P: QuoteProvider>(
+    provider: &Arc<P>,
+    symbols: Vec<String>,
+    date: NaiveDate,
+    raw_close: bool,
+) -> io::Result<Vec<AsOfClose>> {
+    let tasks = symbols.into_iter().map(|symbol| {
+        let provider = Arc::clone(provider);
+        tokio::spawn(async move {
+            let close = provider::close_as_of(provider.as_ref(), &symbol, date, raw_close).await?;
+            Ok(close.map(|(day, close)| AsOfClose {
+                symbol,
+                as_of: date,
+                date: day,
+                close,
+                prior_trading_day: day != date,
+            })) as io::Result<Option<AsOfClose>>
+        })
+    });
+    let mut closes = vec![];
+    for result in futures_util::future::join_all(tasks).await {
+        match result {
+            Ok(close) => closes.extend(close?),
+            Err(err) => eprintln!("{err:?}"),
+        }
+    }
+    Ok(closes)
+}
 
-    let response = provider
-        .get_quote_history(symbol, *beginning, *end)
-        .await
-        .map_err(|_| Error::from(ErrorKind::InvalidData))?;
-    let mut quotes = response
-        .quotes()
-        .map_err(|_| Error::from(ErrorKind::InvalidData))?;
-    if quotes.is_empty() {
-        Ok(vec![])
-    } else {
-        quotes.sort_by_cached_key(|k| k.timestamp);
-        Ok(quotes.iter().map(|q| q.adjclose as f64).collect())
+///
+/// Fetch and process every symbol in its own task, all sharing the provider.
+/// Symbols that failed to fetch are returned separately.
+///
+async fn run_symbols_report<P: QuoteProvider>(
+    provider: &Arc<P>,
+    symbols: Vec<String>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    settings: &ReportSettings,
+) -> (Vec<SymbolReport>, Vec<SymbolError>) {
+    let progress = settings
+        .progress
+        .then(|| Arc::new(Progress::new(symbols.len(), atty::is(atty::Stream::Stderr))));
+    let budget = settings
+        .retry_budget
+        .map(|retries| Arc::new(RetryBudget::new(retries)));
+    let retry = Retry {
+        retries: settings.retries,
+        delay: RETRY_DELAY,
+        rate_limit_delay: RATE_LIMIT_DELAY,
+        jitter: settings.jitter,
+    };
+    let tasks = symbols.into_iter().map(|symbol| {
+        let progress = progress.clone();
+        let provider = Arc::clone(provider);
+        let budget = budget.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            let series = provider::fetch_series_retrying(
+                provider.as_ref(),
+                &symbol,
+                from,
+                to,
+                settings.chunk,
+                retry,
+                budget.as_deref(),
+            )
+            .await;
+            if let Some(progress) = progress {
+                progress.complete_one();
+            }
+            let mut series = series.map_err(|err| SymbolError::new(&symbol, &err))?;
+            if let (Some(latest), Some(last)) = (&settings.latest, series.timestamps.last()) {
+                latest.record(&symbol, *last);
+            }
+            if let Some(dir) = &settings.dump_raw_quotes {
+                let path = dir.join(format!("{symbol}.csv"));
+                if let Err(err) = std::fs::write(&path, series.quotes_csv()) {
+                    eprintln!(
+                        "can't dump the quotes of {symbol} to {}: {err}",
+                        path.display()
+                    );
+                }
+            }
+            if settings.raw_close {
+                series.use_raw_closes();
+            }
+            if settings.dedup_closes {
+                series.dedup_closes();
+            }
+            if let Some(n) = settings.tail {
+                series.tail(n);
+            }
+            if let Some(min) = settings.min_avg_volume {
+                if let Some(avg) = below_min_volume(&series, min) {
+                    eprintln!("skipping {symbol}: average volume {avg:.0} is below {min}");
+                    return Ok(None);
+                }
+            }
+            if settings.explain {
+                eprint!(
+                    "{}",
+                    explain::explain_report(&symbol, &series.closes, settings.sma_window)
+                );
+            }
+            if let Some(bins) = settings.histogram_bins {
+                print_histogram(&symbol, &series.closes, bins, settings.format);
+            }
+            let mut report = process_series(&symbol, &series, &from, &settings);
+            if let (Some(report), Some(command)) = (&mut report, settings.external_signal) {
+                let closes = series.closes;
+                match tokio::task::spawn_blocking(move || external::run_external(&command, &closes))
+                    .await
+                {
+                    Ok(Ok(values)) => report.external = Some(values),
+                    Ok(Err(err)) => eprintln!("external signal for {symbol} failed: {err}"),
+                    Err(err) => eprintln!("{err:?}"),
+                }
+            }
+            Ok(report) as Result<Option<SymbolReport>, SymbolError>
+        })
+    });
+    let (mut reports, mut failures) = (vec![], vec![]);
+    for result in futures_util::future::join_all(tasks).await {
+        match result {
+            Ok(Ok(report)) => reports.extend(report),
+            Ok(Err(failure)) => failures.push(failure),
+            Err(err) => eprintln!("{err:?}"),
+        }
     }
+    (reports, failures)
 }
 
-async fn run_symbols_report(
+///
+/// Fetch the series of every symbol for the side effect of filling the
+/// cache, without reporting on them. Symbols that failed to fetch are
+/// returned.
+///
+async fn prefetch<P: QuoteProvider>(
+    provider: &Arc<P>,
     symbols: Vec<String>,
     from: DateTime<Utc>,
     to: DateTime<Utc>,
-) -> io::Result<()> {
+    settings: &ReportSettings,
+) -> Vec<SymbolError> {
+    let retry = Retry {
+        retries: settings.retries,
+        delay: RETRY_DELAY,
+        rate_limit_delay: RATE_LIMIT_DELAY,
+        jitter: settings.jitter,
+    };
+    let chunk = settings.chunk;
     let tasks = symbols.into_iter().map(|symbol| {
+        let provider = Arc::clone(provider);
         tokio::spawn(async move {
-            let closes = fetch_closing_data(&symbol, &from, &to).await?;
-            process_closing_data(&symbol, &closes, &from);
-            Ok(()) as io::Result<()>
+            provider::fetch_series_retrying(
+                provider.as_ref(),
+                &symbol,
+                from,
+                to,
+                chunk,
+                retry,
+                None,
+            )
+            .await
+            .map_err(|err| SymbolError::new(&symbol, &err))
         })
     });
+    let mut failures = vec![];
     for result in futures_util::future::join_all(tasks).await {
         match result {
-            Ok(report) => {
-                if let Err(err) = report {
-                    return Err(err);
-                }
+            Ok(Ok(_)) => {}
+            Ok(Err(failure)) => failures.push(failure),
+            Err(err) => eprintln!("{err:?}"),
+        }
+    }
+    failures
+}
+
+///
+/// Prefetch the symbols as `--prefetch` asks, reporting the failures on
+/// stderr. Nothing is written to the output, which isn't even opened.
+///
+async fn run_prefetch<P: QuoteProvider>(
+    provider: &Arc<P>,
+    symbols: Vec<String>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    settings: &ReportSettings,
+) -> io::Result<()> {
+    let failures = prefetch(provider, symbols, from, to, settings).await;
+    for failure in &failures {
+        eprintln!("can't prefetch {}: {}", failure.symbol, failure.error);
+    }
+    failures
+        .into_iter()
+        .next()
+        .map_or(Ok(()), |failure| Err(failure.into()))
+}
+
+///
+/// The average daily volume of the series if it's below `min`. Empty series
+/// are left for the report to skip.
+///
+fn below_min_volume(series: &Series, min: u64) -> Option<f64> {
+    #[allow(clippy::cast_precision_loss)]
+    AverageVolume
+        .calculate(&series.bars)
+        .filter(|avg| *avg < min as f64)
+}
+
+fn process_series(
+    symbol: &str,
+    series: &Series,
+    from: &DateTime<Utc>,
+    settings: &ReportSettings,
+) -> Option<SymbolReport> {
+    if series.is_empty() {
+        return None;
+    }
+    let closes = &series.closes;
+    // min/max of the period. unwrap() because those are Option types
+    let period_max: f64 = MaxPrice.calculate(closes).unwrap();
+    let period_min: f64 = MinPrice.calculate(closes).unwrap();
+    let last_price = *closes.last().unwrap_or(&0.0);
+    let (_, pct_change) = PriceDifference.calculate(closes).unwrap_or((0.0, 0.0));
+    let seed = settings
+        .seed
+        .as_ref()
+        .and_then(|seeds| seeds.get(symbol))
+        .map_or(&[][..], Vec::as_slice);
+    let sma = signals::seeded(seed, closes, |closes| {
+        WindowedSMA {
+            window_size: settings.sma_window,
+        }
+        .calculate(closes)
+    })
+    .unwrap_or_default();
+
+    Some(SymbolReport {
+        period_start: *from,
+        symbol: symbol.to_string(),
+        last_price,
+        pct_change: pct_change * 100.0,
+        period_min,
+        period_max,
+        sma: *sma.last().unwrap_or(&0.0),
+        last_close_time: *series.timestamps.last().unwrap_or(from),
+        sma_series: settings.series.then(|| {
+            let warm = &sma[settings.warmup.min(sma.len())..];
+            series_points(&series.timestamps, warm, settings.max_points)
+        }),
+        external: None,
+    })
+}
+
+///
+/// Align a series signal, whose last value belongs to the last close, with
+/// the timestamps of the closes and downsample it to `max_points`.
+///
+fn series_points(
+    timestamps: &[DateTime<Utc>],
+    values: &[f64],
+    max_points: Option<usize>,
+) -> Vec<SeriesPoint> {
+    let offset = timestamps.len().saturating_sub(values.len());
+    let points: Vec<_> = timestamps[offset..]
+        .iter()
+        .zip(values)
+        .map(|(timestamp, value)| SeriesPoint {
+            timestamp: *timestamp,
+            value: *value,
+        })
+        .collect();
+    match max_points {
+        Some(max_points) if max_points < points.len() => {
+            #[allow(clippy::cast_precision_loss)]
+            let xy: Vec<_> = points
+                .iter()
+                .map(|p| (p.timestamp.timestamp() as f64, p.value))
+                .collect();
+            downsample::lttb(&xy, max_points)
+                .into_iter()
+                .map(|i| points[i])
+                .collect()
+        }
+        _ => points,
+    }
+}
+
+///
+/// Print the distribution of daily returns to stderr, as JSON if that's the
+/// output format and as an ASCII bar chart otherwise.
+///
+fn print_histogram(symbol: &str, closes: &[f64], bins: usize, format: OutputFormat) {
+    let histogram = histogram::return_histogram(&signals::daily_returns(closes), bins);
+    if format == OutputFormat::Json {
+        eprintln!("{}", histogram::render_histogram_json(symbol, &histogram));
+    } else {
+        eprint!("{}", histogram::render_histogram(symbol, &histogram));
+    }
+}
+
+///
+/// Run a single report (and its comparison, if any), store its reports in the
+/// database, render the results and post them to the webhook. With `alerts`,
+/// only reports that changed since the last tick are rendered and posted.
+/// Webhook failures are only logged.
+///
+/// A symbol that failed to fetch fails the whole tick, except in JSON where
+/// an error object per symbol follows the rows instead, on its own line or at
+/// the end of the array.
+///
+#[allow(clippy::too_many_arguments)]
+async fn run_tick<P: QuoteProvider>(
+    provider: &Arc<P>,
+    symbols: &[String],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    compare: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    settings: &ReportSettings,
+    webhook: Option<&Webhook>,
+    alerts: Option<&mut Alerts>,
+) -> io::Result<Vec<u8>> {
+    let (reports, failures) =
+        run_symbols_report(provider, symbols.to_vec(), from, to, settings).await;
+    let mut failures = inline_failures(settings, failures)?;
+    store_reports(settings, &reports)?;
+    let mut rendered;
+    let posted = if let Some((compare_from, compare_to)) = compare {
+        let (previous, previous_failures) = run_symbols_report(
+            provider,
+            symbols.to_vec(),
+            compare_from,
+            compare_to,
+            settings,
+        )
+        .await;
+        failures.extend(inline_failures(settings, previous_failures)?);
+        let diffs = diff_reports(&reports, &previous);
+        rendered = output::render_bytes(settings.format, &settings.style, &diffs);
+        match webhook {
+            Some(webhook) => webhook.post(&diffs).await,
+            None => Ok(()),
+        }
+    } else {
+        let changed;
+        let emitted = match alerts {
+            Some(alerts) => {
+                changed = alerts.filter(&reports);
+                &changed
             }
-            Err(err) => eprintln!("{:?}", err),
+            None => &reports,
+        };
+        rendered = output::render_bytes(settings.format, &settings.style, emitted);
+        match webhook {
+            Some(webhook) => webhook.post(emitted).await,
+            None => Ok(()),
         }
+    };
+    if let Err(err) = posted {
+        eprintln!("webhook failed: {err}");
     }
-    Ok(())
+    if settings.summary {
+        if let Some(summary) = report::summarize(&reports) {
+            rendered.extend(output::render_bytes(
+                settings.format,
+                &settings.style,
+                &[summary],
+            ));
+        }
+    }
+    append_failures(settings, &mut rendered, &failures);
+    Ok(rendered)
+}
+
+///
+/// Insert the reports of a tick into the database, if there is one.
+///
+fn store_reports(settings: &ReportSettings, reports: &[SymbolReport]) -> io::Result<()> {
+    match &settings.sqlite {
+        Some(db) => db
+            .lock()
+            .expect("the database is never poisoned")
+            .insert_tick(Utc::now(), reports),
+        None => Ok(()),
+    }
+}
+
+///
+/// Append the JSON error objects of `failures` to the rendered rows, a line
+/// each or at the end of the array.
+///
+fn append_failures<F: Serialize>(
+    settings: &ReportSettings,
+    rendered: &mut Vec<u8>,
+    failures: &[F],
+) {
+    if settings.style.json_array && !failures.is_empty() {
+        append_to_array(rendered, failures);
+    } else {
+        for failure in failures {
+            serde_json::to_writer(&mut *rendered, failure)
+                .expect("errors always serialize to JSON");
+            rendered.push(b'\n');
+        }
+    }
+}
+
+///
+/// Append `failures` to the compact JSON array rendered on the single line
+/// of `rendered`.
+///
+fn append_to_array<F: Serialize>(rendered: &mut Vec<u8>, failures: &[F]) {
+    // drop the closing bracket and line break
+    rendered.truncate(rendered.len() - 2);
+    for failure in failures {
+        if rendered.len() > 1 {
+            rendered.push(b',');
+        }
+        serde_json::to_writer(&mut *rendered, failure).expect("errors always serialize to JSON");
+    }
+    rendered.extend_from_slice(b"]\n");
+}
+
+///
+/// The failures to render with the rows, or the first one as an error unless
+/// the format is JSON.
+///
+fn inline_failures(
+    settings: &ReportSettings,
+    mut failures: Vec<SymbolError>,
+) -> io::Result<Vec<SymbolError>> {
+    if settings.format != OutputFormat::Json && !failures.is_empty() {
+        return Err(failures.swap_remove(0).into());
+    }
+    Ok(failures)
+}
+
+///
+/// Run each spec of a batch once. Specs which fail are only logged so the
+/// others still run.
+///
+async fn run_batch<P: QuoteProvider>(
+    provider: &Arc<P>,
+    specs: &[BatchSpec],
+    settings: &ReportSettings,
+    webhook: Option<&Webhook>,
+) -> Vec<u8> {
+    let mut rendered = vec![];
+    for spec in specs {
+        match run_spec(provider, spec, settings, webhook).await {
+            Ok(spec_rendered) => rendered.extend(spec_rendered),
+            Err(err) => eprintln!("batch '{}' failed: {err}", spec.label()),
+        }
+    }
+    rendered
 }
 
-fn process_closing_data(symbol: &str, closes: &[f64], from: &DateTime<Utc>) {
-    if !closes.is_empty() {
-        // min/max of the period. unwrap() because those are Option types
-        let period_max: f64 = MaxPrice.calculate(closes).unwrap();
-        let period_min: f64 = MinPrice.calculate(closes).unwrap();
-        let last_price = *closes.last().unwrap_or(&0.0);
-        let (_, pct_change) = PriceDifference.calculate(closes).unwrap_or((0.0, 0.0));
-        let sma = WindowedSMA { window_size: 30 }
-            .calculate(closes)
-            .unwrap_or_default();
-
-        // a simple way to output CSV data
-        println!(
-            "{},{},${:.2},{:.2}%,${:.2},${:.2},${:.2}",
-            from.to_rfc3339(),
-            symbol,
-            last_price,
-            pct_change * 100.0,
-            period_min,
-            period_max,
-            sma.last().unwrap_or(&0.0)
+///
+/// Run a single spec of a batch like a tick, with only the signals it picked.
+/// Its output starts with a `#` comment line of its label in the formats
+/// whose rows don't carry the label, followed by a header of the rows'
+/// columns where the format has one.
+///
+async fn run_spec<P: QuoteProvider>(
+    provider: &Arc<P>,
+    spec: &BatchSpec,
+    settings: &ReportSettings,
+    webhook: Option<&Webhook>,
+) -> io::Result<Vec<u8>> {
+    let symbols = parse_symbols(&spec.symbols)?;
+    let settings = ReportSettings {
+        sma_window: spec.sma_window,
+        ..settings.clone()
+    };
+    let to = spec.to.unwrap_or_else(Utc::now);
+    let (reports, failures) = run_symbols_report(provider, symbols, spec.from, to, &settings).await;
+    let failures = inline_failures(&settings, failures)?;
+    store_reports(&settings, &reports)?;
+    let summary = report::summarize(&reports).filter(|_| settings.summary);
+    let rows: Vec<_> = reports
+        .into_iter()
+        .map(|report| BatchRow { spec, report })
+        .collect();
+    if let Some(webhook) = webhook {
+        if let Err(err) = webhook.post(&rows).await {
+            eprintln!("webhook failed: {err}");
+        }
+    }
+    let mut rendered = vec![];
+    if matches!(
+        settings.format,
+        OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::CsvWide | OutputFormat::Table
+    ) {
+        rendered.extend(format!("# {}\n", spec.label()).into_bytes());
+    }
+    let columns = output::columns(&rows).join(",");
+    if let Some(header) =
+        output::delimited(settings.format, &settings.style, &columns).filter(|_| !settings.quiet)
+    {
+        rendered.extend(header.into_bytes());
+        rendered.push(b'\n');
+    }
+    rendered.extend(output::render_bytes(
+        settings.format,
+        &settings.style,
+        &rows,
+    ));
+    if let Some(summary) = summary {
+        rendered.extend(output::render_bytes(
+            settings.format,
+            &settings.style,
+            &[summary],
+        ));
+    }
+    let failures: Vec<_> = failures
+        .iter()
+        .map(|failure| {
+            let mut json = serde_json::to_value(failure).expect("errors always serialize to JSON");
+            json["label"] = spec.label().into();
+            json
+        })
+        .collect();
+    append_failures(&settings, &mut rendered, &failures);
+    Ok(rendered)
+}
+
+///
+/// Report on closes given one per line, as if they were the daily closes of
+/// `symbol` up to today.
+///
+fn stdin_report(
+    symbol: &str,
+    input: &str,
+    settings: &ReportSettings,
+) -> io::Result<Option<SymbolReport>> {
+    let closes = series::parse_closes(input)?;
+    let days = i64::try_from(closes.len().saturating_sub(1)).unwrap_or(i64::MAX);
+    let start = Utc::today().and_hms(0, 0, 0) - chrono::Duration::days(days);
+    let mut series = Series::from_closes(closes, start);
+    if settings.dedup_closes {
+        series.dedup_closes();
+    }
+    if let Some(n) = settings.tail {
+        series.tail(n);
+    }
+    if settings.explain {
+        eprint!(
+            "{}",
+            explain::explain_report(symbol, &series.closes, settings.sma_window)
         );
     }
+    Ok(process_series(symbol, &series, &start, settings))
+}
+
+///
+/// Read the closes from stdin and write their report once.
+///
+fn run_stdin(opts: &Opts, settings: &ReportSettings) -> io::Result<()> {
+    let input = io::read_to_string(io::stdin())?;
+    let reports: Vec<_> = stdin_report(&opts.stdin_symbol, &input, settings)?
+        .into_iter()
+        .collect();
+    let header =
+        output::header::<SymbolReport>(settings.format, &settings.style).filter(|_| !opts.quiet);
+    open_sink(opts, header.as_deref())?.write_tick(
+        Utc::now(),
+        output::render_bytes(settings.format, &settings.style, &reports),
+    )
+}
+
+///
+/// Reject delimiters that can't separate fields, JSON arrays in other formats,
+/// compression of anything but JSON lines, header comments without a header
+/// line, the wide CSV without series, HTML documents split across files, row
+/// kinds or batch specs and combinations the binary formats can't support:
+/// they're only available with their features, aren't meant for terminals
+/// and only hold one kind of row per run, which for protobuf must be reports.
+///
+fn check_format(opts: &Opts) -> io::Result<()> {
+    if opts.json_array && opts.output_format != OutputFormat::Json {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--json-array needs the json format",
+        ));
+    }
+    if opts.gzip && (opts.output_format != OutputFormat::Json || opts.json_array) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--gzip needs the json format without --json-array",
+        ));
+    }
+    if opts.header_comments && !matches!(opts.output_format, OutputFormat::Csv | OutputFormat::Tsv)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--header-comments needs the csv or tsv format",
+        ));
+    }
+    if opts.output_format == OutputFormat::CsvWide && !opts.series {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the csv-wide format needs --series",
+        ));
+    }
+    if opts.output_format == OutputFormat::Html
+        && (opts.rotate.is_some() || opts.summary || opts.batch.is_some())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the html format can't be used with --rotate, --summary or --batch",
+        ));
+    }
+    if matches!(opts.delimiter, '"' | '\n' | '\r') || !opts.delimiter.is_ascii() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the delimiter must be ASCII and can't be a quote or a line break",
+        ));
+    }
+    let (name, enabled) = match opts.output_format {
+        OutputFormat::Msgpack => ("msgpack", cfg!(feature = "msgpack")),
+        OutputFormat::Protobuf => ("protobuf", cfg!(feature = "protobuf")),
+        _ => return Ok(()),
+    };
+    if !enabled {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("the {name} format needs a build with `--features {name}`"),
+        ));
+    }
+    if opts.output.is_none() || opts.tee || opts.batch.is_some() || opts.summary {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("the {name} format needs --output and can't be used with --tee, --batch or --summary"),
+        ));
+    }
+    if opts.output_format == OutputFormat::Protobuf
+        && (opts.compare_from.is_some() || opts.as_of.is_some())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the protobuf format only holds reports and can't be used with --compare-from or --as-of",
+        ));
+    }
+    Ok(())
+}
+
+///
+/// The header printed once before the ticks, matching their rows. With
+/// `--header-comments`, it's preceded by a `#` line each for the symbols,
+/// the moving average window, the period and the signals of the reports.
+///
+fn tick_header(
+    opts: &Opts,
+    symbols: &[String],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    compare: bool,
+) -> Option<String> {
+    let style = style(opts);
+    let header = if opts.quiet {
+        None
+    } else if compare {
+        output::header::<ReportDiff>(opts.output_format, &style)
+    } else if opts.external_signal.is_some() {
+        output::delimited(
+            opts.output_format,
+            &style,
+            &format!("{},external", SymbolReport::CSV_HEADER),
+        )
+    } else {
+        output::header::<SymbolReport>(opts.output_format, &style)
+    }?;
+    if !opts.header_comments {
+        return Some(header);
+    }
+    let mut signals = vec![
+        "PriceDifference".to_string(),
+        "MinPrice".to_string(),
+        "MaxPrice".to_string(),
+        format!("WindowedSMA({SMA_WINDOW})"),
+    ];
+    if let Some(command) = &opts.external_signal {
+        signals.push(format!("external({command})"));
+    }
+    Some(format!(
+        "# symbols: {}\n# window: {SMA_WINDOW}\n# from: {}\n# to: {}\n# signals: {}\n{header}",
+        symbols.join(","),
+        from.to_rfc3339(),
+        to.to_rfc3339(),
+        signals.join(",")
+    ))
+}
+
+fn open_sink(opts: &Opts, header: Option<&str>) -> io::Result<Sink> {
+    match &opts.output {
+        // every tick is a complete document, the file holds the latest
+        Some(path) if opts.output_format == OutputFormat::Html => {
+            let sink = Sink::replace(path);
+            Ok(if opts.tee {
+                sink.tee(Sink::stdout(header))
+            } else {
+                sink
+            })
+        }
+        Some(path) if opts.gzip => {
+            let sink = Sink::gzip(path)?;
+            Ok(if opts.tee {
+                sink.tee(Sink::stdout(header))
+            } else {
+                sink
+            })
+        }
+        Some(path) if opts.tee => {
+            Ok(Sink::file(path, opts.rotate, header)?.tee(Sink::stdout(header)))
+        }
+        Some(path) => Sink::file(path, opts.rotate, header),
+        None => Ok(Sink::stdout(header)),
+    }
+}
+
+fn style(opts: &Opts) -> Style {
+    Style {
+        precision: opts.precision,
+        color: opts.color.enabled(
+            opts.output.is_none() && atty::is(atty::Stream::Stdout),
+            std::env::var("NO_COLOR").ok().as_deref(),
+        ),
+        timestamps: opts.timestamp_format.clone(),
+        delimiter: opts.delimiter,
+        quote: opts.quote_style,
+        json_array: opts.json_array,
+    }
+}
+
+///
+/// The settings shared by every tick of the run.
+///
+fn report_settings(opts: &Opts) -> io::Result<ReportSettings> {
+    if opts.tail == Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--tail needs at least one close",
+        ));
+    }
+    if opts.histogram == Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--histogram needs at least one bin",
+        ));
+    }
+    if opts
+        .retry_jitter
+        .is_some_and(|fraction| !(0.0..=f64::MAX).contains(&fraction))
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--retry-jitter needs a finite fraction of at least 0",
+        ));
+    }
+    let seed = match &opts.seed {
+        Some(dir) => Some(series::read_closes_dir(dir)?),
+        None => None,
+    };
+    for dir in opts.dump_raw_quotes.iter().chain(&opts.cache_dir) {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(ReportSettings {
+        format: opts.output_format,
+        style: style(opts),
+        quiet: opts.quiet,
+        summary: opts.summary,
+        histogram_bins: opts.histogram,
+        explain: opts.explain,
+        progress: opts.progress,
+        series: opts.series,
+        tail: opts.tail,
+        warmup: opts.warmup,
+        max_points: opts.max_points,
+        sma_window: SMA_WINDOW,
+        min_avg_volume: opts.min_avg_volume,
+        retries: opts.retries.unwrap_or_default(),
+        retry_budget: opts.retry_budget,
+        jitter: opts.retry_jitter.map(|fraction| Jitter {
+            seed: opts
+                .random_seed
+                .unwrap_or_else(|| Rng::from_entropy().next_u64()),
+            fraction,
+        }),
+        raw_close: opts.raw_close,
+        dedup_closes: opts.dedup_closes,
+        external_signal: opts.external_signal.as_deref().map(Arc::from),
+        seed: seed.map(Arc::new),
+        dump_raw_quotes: opts.dump_raw_quotes.as_deref().map(Arc::from),
+        // shared by every tick
+        latest: opts
+            .exit_on_stale
+            .map(|_| Arc::new(LatestQuotes::default())),
+        chunk: opts
+            .chunk_days
+            .map(|days| chrono::Duration::days(days.into())),
+        sqlite: match &opts.sqlite {
+            Some(path) => Some(Arc::new(Mutex::new(ReportDb::open(path)?))),
+            None => None,
+        },
+    })
+}
+
+///
+/// The period of a tick starting at `now`. Without a fixed end `to`, it ends
+/// at `now`, and with `--since-open` it starts at the last market open before
+/// `now` instead of at `from`.
+///
+fn tick_period(
+    opts: &Opts,
+    from: DateTime<Utc>,
+    to: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let to = to.unwrap_or(now);
+    if opts.since_open {
+        (
+            clamp_from(market::last_open(now), to, opts.max_history_days),
+            to,
+        )
+    } else {
+        (from, to)
+    }
+}
+
+///
+/// Report every interval until the process is stopped, backing off after
+/// failed reports. With `--parallel-ticks`, each report runs in a task of its
+/// own so a slow one doesn't delay the next, and reports are written in the
+/// order they finish. Each written report counts as a sign of life for the
+/// health probe, if any. With `--exit-on-stale`, the loop ends with an error
+/// once that many ticks in a row brought no newer quote for any symbol.
+///
+/// Each tick reports on its own `tick_period`.
+///
+#[allow(clippy::too_many_arguments)]
+async fn report_loop<P: QuoteProvider>(
+    opts: &Opts,
+    provider: &Arc<P>,
+    watchlist: &mut Watchlist,
+    mut sink: Sink,
+    from: DateTime<Utc>,
+    to: Option<DateTime<Utc>>,
+    compare: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    settings: ReportSettings,
+    webhook: Option<Arc<Webhook>>,
+    health: Option<Arc<Health>>,
+) -> io::Result<()> {
+    if opts.parallel_ticks == Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--parallel-ticks needs at least one tick",
+        ));
+    }
+    if opts.exit_on_stale == Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "--exit-on-stale needs at least one tick",
+        ));
+    }
+    let mut stale = StaleTicks::default();
+    let mut alerts = opts.alert_threshold.map(Alerts::new);
+    let mut backoff = Backoff::new(
+        Duration::from_secs(opts.interval),
+        Duration::from_secs(opts.max_interval),
+    );
+    // overlapping ticks run in their own tasks and report back when done
+    let mut pool = opts.parallel_ticks.map(TickPool::new);
+    let mut interval = tokio::time::interval(backoff.delay());
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        let tick = match &mut pool {
+            None => {
+                interval.tick().await;
+                let (from, to) = tick_period(opts, from, to, Utc::now());
+                run_tick(
+                    provider,
+                    watchlist.reload(),
+                    from,
+                    to,
+                    compare,
+                    &settings,
+                    webhook.as_deref(),
+                    alerts.as_mut(),
+                )
+                .await
+            }
+            Some(pool) => tokio::select! {
+                _ = interval.tick() => {
+                    let provider = Arc::clone(provider);
+                    let symbols = watchlist.reload().to_vec();
+                    let webhook = webhook.clone();
+                    let settings = settings.clone();
+                    let (from, to) = tick_period(opts, from, to, Utc::now());
+                    pool.spawn(async move {
+                        run_tick(
+                            &provider,
+                            &symbols,
+                            from,
+                            to,
+                            compare,
+                            &settings,
+                            webhook.as_deref(),
+                            None,
+                        )
+                        .await
+                    })
+                    .await;
+                    continue;
+                }
+                Some(tick) = pool.next() => tick,
+            },
+        };
+        let delay = match tick.and_then(|rendered| sink.write_tick(Utc::now(), rendered)) {
+            Ok(()) => {
+                if let Some(health) = &health {
+                    health.succeed(std::time::Instant::now());
+                }
+                backoff.succeed()
+            }
+            Err(err) => {
+                eprintln!("report failed: {err}");
+                backoff.fail()
+            }
+        };
+        if let (Some(limit), Some(latest)) = (opts.exit_on_stale, &settings.latest) {
+            if stale.observe(latest.snapshot()) >= limit {
+                return Err(Error::new(
+                    ErrorKind::TimedOut,
+                    format!("no newer quotes for any symbol in {limit} reports"),
+                ));
+            }
+        }
+        if delay != interval.period() {
+            interval = tokio::time::interval_at(Instant::now() + delay, delay);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     let opts = Opts::parse();
-    let from: DateTime<Utc> = opts.from.parse().expect("Couldn't parse 'from' date");
-    let to = Utc::now();
+    check_format(&opts)?;
+    let mut watchlist = match &opts.symbols_file {
+        Some(path) => Watchlist::file(path)?,
+        None => Watchlist::fixed(parse_symbols(&opts.symbols)?),
+    };
+    let (yahoo, interval) = if opts.since_open {
+        (
+            provider::Yahoo::with_interval(INTRADAY_INTERVAL),
+            INTRADAY_INTERVAL,
+        )
+    } else {
+        (provider::Yahoo::new(), DAILY_INTERVAL)
+    };
+    let provider = Arc::new(Cache::new(yahoo, opts.cache_dir.clone(), interval));
+    if let Some(as_of) = &opts.as_of {
+        let date = parse_date(as_of)?.date().naive_utc();
+        let closes = run_as_of(
+            &provider,
+            watchlist.symbols().to_vec(),
+            date,
+            opts.raw_close,
+        )
+        .await?;
+        return open_sink(
+            &opts,
+            output::header::<AsOfClose>(opts.output_format, &style(&opts))
+                .filter(|_| !opts.quiet)
+                .as_deref(),
+        )?
+        .write_tick(
+            Utc::now(),
+            output::render_bytes(opts.output_format, &style(&opts), &closes),
+        );
+    }
+    let settings = report_settings(&opts)?;
+    let webhook = opts
+        .webhook
+        .as_deref()
+        .map(|url| Webhook::new(url, Duration::from_secs(opts.webhook_timeout)).map(Arc::new))
+        .transpose()
+        .map_err(Error::other)?;
+    if let Some(path) = &opts.batch {
+        let specs = batch::parse_batch(&std::fs::read_to_string(path)?)?;
+        let rendered = run_batch(&provider, &specs, &settings, webhook.as_deref()).await;
+        return open_sink(&opts, None)?.write_tick(Utc::now(), rendered);
+    }
+    if opts.stdin {
+        return run_stdin(&opts, &settings);
+    }
 
-    // a simple way to output a CSV header
-    println!("period start,symbol,price,change %,min,max,30d avg");
-    let mut interval = tokio::time::interval(Duration::from_secs(30));
-    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
-    let symbols: Vec<_> = opts.symbols.split(',').map(ToString::to_string).collect();
-    loop {
-        interval.tick().await;
-        run_symbols_report(symbols.clone(), from, to).await?;
+    let fixed_to = opts.to.as_deref().map(parse_date).transpose()?;
+    let to = fixed_to.unwrap_or_else(Utc::now);
+    let from = if opts.since_open {
+        market::last_open(Utc::now())
+    } else {
+        parse_date(opts.from.as_deref().unwrap_or_default())?
+    };
+    let from = clamp_from(from, to, opts.max_history_days);
+    let compare = match opts.compare_from.as_deref().zip(opts.compare_to.as_deref()) {
+        Some((from, to)) => {
+            let to = parse_date(to)?;
+            Some((clamp_from(parse_date(from)?, to, opts.max_history_days), to))
+        }
+        None => None,
+    };
+    if opts.prefetch {
+        return run_prefetch(&provider, watchlist.symbols().to_vec(), from, to, &settings).await;
+    }
+
+    let header = tick_header(&opts, watchlist.symbols(), from, to, compare.is_some());
+    if opts.interval == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'interval' must be at least one second",
+        ));
     }
-    // Ok(())
+    let sink = open_sink(&opts, header.as_deref())?;
+    let health = match &opts.health_addr {
+        Some(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            let health = Arc::new(Health::new(Duration::from_secs(opts.health_stale_after)));
+            tokio::spawn(health::serve(listener, Arc::clone(&health)));
+            Some(health)
+        }
+        None => None,
+    };
+    report_loop(
+        &opts,
+        &provider,
+        &mut watchlist,
+        sink,
+        from,
+        fixed_to,
+        compare,
+        settings,
+        webhook,
+        health,
+    )
+    .await
 }
 
 #[cfg(test)]
 mod tests {
     #![allow(non_snake_case)]
     use super::*;
+    use crate::approx::{assert_approx_eq, assert_some_approx_eq};
+
+    fn settings() -> ReportSettings {
+        ReportSettings {
+            format: OutputFormat::Csv,
+            style: Style::default(),
+            quiet: false,
+            summary: false,
+            histogram_bins: None,
+            explain: false,
+            progress: false,
+            series: false,
+            tail: None,
+            warmup: 0,
+            max_points: None,
+            sma_window: SMA_WINDOW,
+            min_avg_volume: None,
+            retries: 0,
+            retry_budget: None,
+            jitter: None,
+            raw_close: false,
+            dedup_closes: false,
+            external_signal: None,
+            seed: None,
+            dump_raw_quotes: None,
+            latest: None,
+            chunk: None,
+            sqlite: None,
+        }
+    }
 
     #[test]
-    fn test_PriceDifference_calculate() {
-        let signal = PriceDifference {};
-        assert_eq!(signal.calculate(&[]), None);
-        assert_eq!(signal.calculate(&[1.0]), Some((0.0, 0.0)));
-        assert_eq!(signal.calculate(&[1.0, 0.0]), Some((-1.0, -1.0)));
+    fn test_parse_symbols() {
         assert_eq!(
-            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
-            Some((8.0, 4.0))
+            parse_symbols("aapl, msft ,BMW.de").unwrap(),
+            vec!["AAPL", "MSFT", "BMW.DE"]
         );
+        assert_eq!(parse_symbols("btc-usd").unwrap(), vec!["BTC-USD"]);
+        assert_eq!(parse_symbols("eurusd=x").unwrap(), vec!["EURUSD=X"]);
         assert_eq!(
-            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
-            Some((1.0, 1.0))
+            parse_symbols("^gspc,gc=f,brk-b").unwrap(),
+            vec!["^GSPC", "GC=F", "BRK-B"]
         );
     }
 
     #[test]
-    fn test_MinPrice_calculate() {
-        let signal = MinPrice {};
-        assert_eq!(signal.calculate(&[]), None);
-        assert_eq!(signal.calculate(&[1.0]), Some(1.0));
-        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(0.0));
+    fn test_clamp_from() {
+        let to = Utc.ymd(2021, 12, 1).and_hms(12, 0, 0);
+        let decade_ago = Utc.ymd(2011, 12, 1).and_hms(0, 0, 0);
+        assert_eq!(
+            clamp_from(decade_ago, to, Some(90)),
+            Utc.ymd(2021, 9, 2).and_hms(12, 0, 0)
+        );
+        let recent = Utc.ymd(2021, 11, 1).and_hms(0, 0, 0);
+        assert_eq!(clamp_from(recent, to, Some(90)), recent);
+        assert_eq!(clamp_from(decade_ago, to, None), decade_ago);
+    }
+
+    #[test]
+    fn test_tick_header_comments() {
+        let opts =
+            Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--header-comments"]).unwrap();
+        let symbols = ["AAPL".to_string(), "MSFT".to_string()];
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(12, 0, 0),
+        );
+        let header = tick_header(&opts, &symbols, from, to, false).unwrap();
+        let lines: Vec<&str> = header.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# symbols: AAPL,MSFT",
+                "# window: 30",
+                "# from: 2021-01-01T00:00:00+00:00",
+                "# to: 2021-01-31T12:00:00+00:00",
+                "# signals: PriceDifference,MinPrice,MaxPrice,WindowedSMA(30)",
+                SymbolReport::CSV_HEADER,
+            ]
+        );
+        check_format(&opts).unwrap();
+
+        let plain = Opts::try_parse_from(["ffl", "--from", "2021-01-01"]).unwrap();
+        assert_eq!(
+            tick_header(&plain, &symbols, from, to, false).as_deref(),
+            Some(SymbolReport::CSV_HEADER)
+        );
+        let json = Opts::try_parse_from([
+            "ffl",
+            "--from",
+            "2021-01-01",
+            "--header-comments",
+            "--output-format",
+            "json",
+        ])
+        .unwrap();
+        assert!(check_format(&json).is_err());
+        assert!(Opts::try_parse_from([
+            "ffl",
+            "--from",
+            "2021-01-01",
+            "--header-comments",
+            "--quiet"
+        ])
+        .is_err());
+        // the comments describe ticks, not closes from stdin or batch labels
+        assert!(Opts::try_parse_from(["ffl", "--stdin", "--header-comments"]).is_err());
+        assert!(Opts::try_parse_from(["ffl", "--batch", "b.txt", "--header-comments"]).is_err());
+    }
+
+    #[test]
+    fn test_check_format_delimiter() {
+        let parse = |delimiter| {
+            Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--delimiter", delimiter]).unwrap()
+        };
+        check_format(&parse(";")).unwrap();
+        assert!(check_format(&parse("\"")).is_err());
+        assert!(check_format(&parse("§")).is_err());
+    }
+
+    #[test]
+    fn test_parse_symbols_file() {
+        let opts = Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--symbols-file", "w.txt"]);
+        assert_eq!(opts.unwrap().symbols_file, Some(PathBuf::from("w.txt")));
+        assert!(Opts::try_parse_from([
+            "ffl",
+            "--from",
+            "2021-01-01",
+            "--symbols-file",
+            "w.txt",
+            "-s",
+            "AAPL"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_symbols_invalid() {
+        for input in [
+            "AA PL",
+            "",
+            "AAPL,",
+            "AAPL,,MSFT",
+            "=X",
+            "EURUSD=",
+            "EUR/USD",
+        ] {
+            let err = parse_symbols(input).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let expected = Utc.ymd(2021, 11, 1).and_hms(0, 0, 0);
+        assert_eq!(parse_date("1635724800").unwrap(), expected);
+        assert_eq!(parse_date("2021-11-01T00:00:00Z").unwrap(), expected);
+        assert_eq!(parse_date("2021-11-01T01:00:00+01:00").unwrap(), expected);
+        assert_eq!(parse_date("2021-11-01").unwrap(), expected);
         assert_eq!(
-            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
-            Some(1.0)
+            parse_date("0").unwrap(),
+            Utc.ymd(1970, 1, 1).and_hms(0, 0, 0)
+        );
+
+        for input in ["", "yesterday", "2021-13-01", "-1", "99999999999999999999"] {
+            let err = parse_date(input).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_series_points() {
+        let start = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let timestamps: Vec<_> = (0..10_000)
+            .map(|i| start + chrono::Duration::days(i))
+            .collect();
+        let values: Vec<_> = (0..9_998).map(f64::from).collect();
+
+        let points = series_points(&timestamps, &values, None);
+        assert_eq!(points.len(), values.len());
+        assert_eq!(points[0].timestamp, timestamps[2]);
+        assert_eq!(points.last().unwrap().timestamp, timestamps[9_999]);
+
+        let points = series_points(&timestamps, &values, Some(200));
+        assert!(points.len() <= 200);
+        assert_eq!(points[0].timestamp, timestamps[2]);
+        assert_approx_eq(points[0].value, 0.0);
+        assert_eq!(points.last().unwrap().timestamp, timestamps[9_999]);
+        assert_some_approx_eq(points.last().map(|p| p.value), 9_997.0);
+    }
+
+    #[tokio::test]
+    async fn test_below_min_volume() {
+        let provider = provider::mock::MockProvider::new(|symbol, _, _| {
+            let volume = if symbol == "ILLQ" { 50 } else { 5_000 };
+            Ok((0..3)
+                .map(|i| yahoo_finance_api::Quote {
+                    volume: volume + i * 10,
+                    ..series::tests::quote(1_635_897_600 + i * 86_400, 1.0)
+                })
+                .collect())
+        });
+        let (from, to) = (
+            Utc.ymd(2021, 11, 3).and_hms(0, 0, 0),
+            Utc.ymd(2021, 11, 6).and_hms(0, 0, 0),
+        );
+        let liquid = provider::fetch_series(&provider, "AAPL", from, to, None)
+            .await
+            .unwrap();
+        let illiquid = provider::fetch_series(&provider, "ILLQ", from, to, None)
+            .await
+            .unwrap();
+
+        assert_eq!(below_min_volume(&liquid, 1_000), None);
+        assert_eq!(below_min_volume(&illiquid, 1_000), Some(60.0));
+        assert_eq!(below_min_volume(&illiquid, 60), None);
+        assert_eq!(below_min_volume(&Series::default(), 1_000), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_run_tick_ordered_output() {
+        let provider = Arc::new(provider::mock::MockProvider::daily());
+        let symbols: Vec<_> = (0..64).map(|i| format!("S{i}")).collect();
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
         );
+        let settings = settings();
+        let rendered = run_tick(&provider, &symbols, from, to, None, &settings, None, None)
+            .await
+            .unwrap();
+        let rendered = String::from_utf8(rendered).unwrap();
+        // one complete line per symbol in the order of the watchlist, no
+        // matter which task finished first
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines.len(), symbols.len());
+        for (line, symbol) in lines.iter().zip(&symbols) {
+            assert_eq!(
+                *line,
+                format!("2021-01-01T00:00:00+00:00,{symbol},$31.00,3000.00%,$1.00,$31.00,$16.50")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_symbols_report_raw_close() {
+        // the stock split 2:1, so the old raw close is twice the adjusted one
+        let provider = Arc::new(provider::mock::MockProvider::new(|_, start, _| {
+            Ok(vec![yahoo_finance_api::Quote {
+                close: 200.0,
+                ..series::tests::quote(start.timestamp().unsigned_abs(), 100.0)
+            }])
+        }));
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        );
+        let symbols = vec!["AAPL".to_string()];
+        let (adjusted, _) =
+            run_symbols_report(&provider, symbols.clone(), from, to, &settings()).await;
+        assert_some_approx_eq(adjusted.first().map(|r| r.last_price), 100.0);
+        let raw = ReportSettings {
+            raw_close: true,
+            ..settings()
+        };
+        let (raw, _) = run_symbols_report(&provider, symbols, from, to, &raw).await;
+        assert_some_approx_eq(raw.first().map(|r| r.last_price), 200.0);
+
+        let closes = run_as_of(
+            &provider,
+            vec!["AAPL".to_string()],
+            NaiveDate::from_ymd(2021, 1, 31),
+            true,
+        )
+        .await
+        .unwrap();
+        assert_some_approx_eq(closes.first().map(|c| c.close), 200.0);
+        // closes read from stdin are neither
+        assert!(Opts::try_parse_from(["ffl", "--stdin", "--raw-close"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_symbols_report_dump_raw_quotes() {
+        // out of order, with a raw close and volume differing from the rest
+        let provider = Arc::new(provider::mock::MockProvider::new(|_, _, _| {
+            Ok(vec![
+                series::tests::quote(1_609_632_000, 3.0),
+                yahoo_finance_api::Quote {
+                    close: 2.0,
+                    volume: 500,
+                    ..series::tests::quote(1_609_459_200, 1.0)
+                },
+                series::tests::quote(1_609_545_600, 2.5),
+            ])
+        }));
+        let dir = sink::tests::temp_dir("dump-raw-quotes");
+        let settings = ReportSettings {
+            dump_raw_quotes: Some(Arc::from(dir.as_path())),
+            raw_close: true,
+            ..settings()
+        };
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        );
+        let symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        let (reports, _) = run_symbols_report(&provider, symbols, from, to, &settings).await;
+        assert_eq!(reports.len(), 2);
+        for symbol in ["AAPL", "MSFT"] {
+            assert_eq!(
+                std::fs::read_to_string(dir.join(format!("{symbol}.csv"))).unwrap(),
+                "timestamp,open,high,low,close,adjclose,volume\n\
+                 2021-01-01T00:00:00+00:00,1,1,1,2,1,500\n\
+                 2021-01-02T00:00:00+00:00,2.5,2.5,2.5,2.5,2.5,1000\n\
+                 2021-01-03T00:00:00+00:00,3,3,3,3,3,1000\n"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefetch() {
+        let dir = sink::tests::temp_dir("prefetch");
+        let cache_dir = dir.join("cache");
+        let (output, raw) = (dir.join("report.csv"), dir.join("raw"));
+        let opts = Opts::try_parse_from([
+            "ffl",
+            "--from",
+            "2021-01-01",
+            "--to",
+            "2021-01-31",
+            "--prefetch",
+            "--cache-dir",
+            cache_dir.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--dump-raw-quotes",
+            raw.to_str().unwrap(),
+        ])
+        .unwrap();
+        let provider = Arc::new(Cache::new(
+            provider::mock::MockProvider::new(|symbol, start, _| {
+                if symbol == "FAIL" {
+                    Err(Error::from(ErrorKind::TimedOut))
+                } else {
+                    Ok(vec![series::tests::quote(
+                        start.timestamp().unsigned_abs(),
+                        1.0,
+                    )])
+                }
+            }),
+            opts.cache_dir.clone(),
+            DAILY_INTERVAL,
+        ));
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        );
+        let symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        let settings = report_settings(&opts).unwrap();
+        run_prefetch(&provider, symbols.clone(), from, to, &settings)
+            .await
+            .unwrap();
+        let mut entries: Vec<_> = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
         assert_eq!(
-            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
-            Some(0.0)
+            entries,
+            [
+                "AAPL_1d_20210101T000000_20210131T000000.csv",
+                "MSFT_1d_20210101T000000_20210131T000000.csv"
+            ]
+        );
+        // no report output at all
+        assert!(!output.exists());
+        assert_eq!(std::fs::read_dir(&raw).unwrap().count(), 0);
+        // the report is answered from the cache
+        let (reports, _) = run_symbols_report(&provider, symbols, from, to, &settings).await;
+        assert_eq!(reports.len(), 2);
+        assert_eq!(provider.inner().call_count(), 2);
+
+        let failures = prefetch(&provider, vec!["FAIL".to_string()], from, to, &settings).await;
+        assert_eq!(failures.len(), 1);
+        assert!(
+            run_prefetch(&provider, vec!["FAIL".to_string()], from, to, &settings)
+                .await
+                .is_err()
         );
+        assert!(Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--prefetch"]).is_err());
+    }
+
+    ///
+    /// Run the report loop with `--exit-on-stale 1` every second against a
+    /// provider answering with a single quote at the time `quote_time` picks
+    /// from the end of the requested period.
+    ///
+    async fn exit_on_stale(
+        name: &str,
+        quote_time: fn(DateTime<Utc>) -> DateTime<Utc>,
+        limit: Duration,
+    ) -> Result<io::Result<()>, tokio::time::error::Elapsed> {
+        let dir = sink::tests::temp_dir(name);
+        let opts = Opts::try_parse_from([
+            "ffl",
+            "--from",
+            "2021-01-01",
+            "--interval",
+            "1",
+            "--exit-on-stale",
+            "1",
+        ])
+        .unwrap();
+        let settings = report_settings(&opts).unwrap();
+        let provider = Arc::new(provider::mock::MockProvider::new(move |_, _, end| {
+            Ok(vec![series::tests::quote(
+                quote_time(end).timestamp().unsigned_abs(),
+                1.0,
+            )])
+        }));
+        let mut watchlist = Watchlist::fixed(vec!["AAPL".to_string()]);
+        let sink = Sink::file(&dir.join("report.csv"), None, None).unwrap();
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        tokio::time::timeout(
+            limit,
+            report_loop(
+                &opts,
+                &provider,
+                &mut watchlist,
+                sink,
+                from,
+                None,
+                None,
+                settings,
+                None,
+                None,
+            ),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_report_loop_exit_on_stale() {
+        // quotes up to the end of each tick's period keep advancing
+        let advancing = exit_on_stale("stale-advancing", |end| end, Duration::from_millis(2500));
+        // the same quote every tick is stale from the second one
+        let frozen = exit_on_stale(
+            "stale-frozen",
+            |_| Utc.ymd(2021, 1, 4).and_hms(0, 0, 0),
+            Duration::from_secs(5),
+        );
+        let (advancing, frozen) = tokio::join!(advancing, frozen);
+        assert!(advancing.is_err(), "the loop is still running");
+        let err = frozen.unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
     }
 
     #[test]
-    fn test_MaxPrice_calculate() {
-        let signal = MaxPrice {};
-        assert_eq!(signal.calculate(&[]), None);
-        assert_eq!(signal.calculate(&[1.0]), Some(1.0));
-        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(1.0));
+    fn test_tick_period() {
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        // Tuesday and Wednesday afternoon in New York
+        let (tuesday, wednesday) = (
+            Utc.ymd(2021, 11, 2).and_hms(18, 0, 0),
+            Utc.ymd(2021, 11, 3).and_hms(18, 0, 0),
+        );
+        let opts = Opts::try_parse_from(["ffl", "--from", "2021-01-01"]).unwrap();
+        assert_eq!(tick_period(&opts, from, None, tuesday), (from, tuesday));
+        assert_eq!(tick_period(&opts, from, None, wednesday), (from, wednesday));
+        let fixed = Utc.ymd(2021, 6, 1).and_hms(0, 0, 0);
         assert_eq!(
-            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
-            Some(10.0)
+            tick_period(&opts, from, Some(fixed), wednesday),
+            (from, fixed)
+        );
+
+        // each tick starts at its own session's open
+        let opts = Opts::try_parse_from(["ffl", "--since-open"]).unwrap();
+        assert_eq!(
+            tick_period(&opts, from, None, tuesday),
+            (Utc.ymd(2021, 11, 2).and_hms(13, 30, 0), tuesday)
         );
         assert_eq!(
-            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
-            Some(6.0)
+            tick_period(&opts, from, None, wednesday),
+            (Utc.ymd(2021, 11, 3).and_hms(13, 30, 0), wednesday)
         );
     }
 
+    #[tokio::test]
+    async fn test_run_tick_influx_timestamps() {
+        // a new close each tick
+        let provider = Arc::new(provider::mock::MockProvider::new(|_, start, end| {
+            Ok(vec![
+                series::tests::quote(start.timestamp().unsigned_abs(), 1.0),
+                series::tests::quote(end.timestamp().unsigned_abs(), 2.0),
+            ])
+        }));
+        let symbols = vec!["AAPL".to_string()];
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let influx = ReportSettings {
+            format: OutputFormat::Influx,
+            ..settings()
+        };
+        let mut timestamps = vec![];
+        for to in [
+            Utc.ymd(2021, 1, 30).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        ] {
+            let rendered = run_tick(&provider, &symbols, from, to, None, &influx, None, None)
+                .await
+                .unwrap();
+            let rendered = String::from_utf8(rendered).unwrap();
+            let timestamp = rendered.trim_end().rsplit(' ').next().unwrap().to_string();
+            assert_eq!(timestamp, to.timestamp_nanos().to_string());
+            timestamps.push(timestamp);
+        }
+        assert_ne!(timestamps[0], timestamps[1]);
+    }
+
+    #[tokio::test]
+    async fn test_run_tick_sqlite() {
+        let provider = Arc::new(provider::mock::MockProvider::daily());
+        let path = sink::tests::temp_dir("run-tick-sqlite").join("reports.db");
+        let stored = ReportSettings {
+            sqlite: Some(Arc::new(Mutex::new(ReportDb::open(&path).unwrap()))),
+            ..settings()
+        };
+        let symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        for to in [
+            Utc.ymd(2021, 1, 30).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        ] {
+            run_tick(&provider, &symbols, from, to, None, &stored, None, None)
+                .await
+                .unwrap();
+        }
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let mut select = connection
+            .prepare("SELECT symbol, last_close_time FROM reports ORDER BY last_close_time, symbol")
+            .unwrap();
+        let rows: Vec<(String, String)> = select
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("AAPL", "2021-01-30T00:00:00+00:00"),
+                ("MSFT", "2021-01-30T00:00:00+00:00"),
+                ("AAPL", "2021-01-31T00:00:00+00:00"),
+                ("MSFT", "2021-01-31T00:00:00+00:00"),
+            ]
+            .map(|(symbol, time)| (symbol.to_string(), time.to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_batch() {
+        let provider = Arc::new(provider::mock::MockProvider::daily());
+        let specs = batch::parse_batch(
+            "--label bad -s AAPL,M$FT --from 2021-01-01 --to 2021-01-31\n\
+             --label tech -s AAPL --from 2021-01-01 --to 2021-01-31 --sma-window 10 --signals change,sma\n",
+        )
+        .unwrap();
+        // the invalid symbol only fails its own spec
+        let rendered = run_batch(&provider, &specs, &settings(), None).await;
+        let rendered = String::from_utf8(rendered).unwrap();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "# tech");
+        assert_eq!(lines[1], "period start,symbol,price,change %,10d avg");
+        assert!(lines[2].contains("AAPL"));
+        assert_eq!(lines[2].split(',').count(), 5);
+
+        // the header has the external column of the rows
+        let external = ReportSettings {
+            external_signal: Some(Arc::from("echo 1 2")),
+            ..settings()
+        };
+        let picked = batch::parse_batch(
+            "-s AAPL --from 2021-01-01 --to 2021-01-31 --sma-window 10 --signals sma,external",
+        )
+        .unwrap();
+        let rendered = run_batch(&provider, &picked, &external, None).await;
+        let rendered = String::from_utf8(rendered).unwrap();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines[0], "# AAPL");
+        assert_eq!(lines[1], "period start,symbol,price,10d avg,external");
+        assert!(lines[2].ends_with(",1.00 2.00"));
+
+        // JSON rows carry the label instead
+        let json = ReportSettings {
+            format: OutputFormat::Json,
+            ..settings()
+        };
+        let rendered = run_batch(&provider, &specs, &json, None).await;
+        let rendered = String::from_utf8(rendered).unwrap();
+        assert_eq!(rendered.lines().count(), 1);
+        let row: serde_json::Value = serde_json::from_str(rendered.trim_end()).unwrap();
+        assert_eq!(row["label"], "tech");
+        assert!(row.get("pct_change").is_some());
+        assert!(row.get("period_min").is_none());
+
+        // as do YAML and Influx rows
+        let yaml = ReportSettings {
+            format: OutputFormat::Yaml,
+            ..settings()
+        };
+        let rendered = run_batch(&provider, &specs, &yaml, None).await;
+        let rows: Vec<serde_json::Value> = serde_yaml::from_slice(&rendered).unwrap();
+        assert_eq!(rows[0]["label"], "tech");
+        let influx = ReportSettings {
+            format: OutputFormat::Influx,
+            ..settings()
+        };
+        let rendered = run_batch(&provider, &specs, &influx, None).await;
+        let rendered = String::from_utf8(rendered).unwrap();
+        assert!(rendered.starts_with("stock,symbol=AAPL,label=tech price="));
+        assert!(rendered.contains(",change_pct="));
+        assert!(!rendered.contains(",min="));
+
+        // HTML documents can't be labelled or concatenated
+        let html =
+            Opts::try_parse_from(["ffl", "--batch", "b.txt", "--output-format", "html"]).unwrap();
+        assert!(check_format(&html).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_tick_failed_symbol() {
+        let provider = Arc::new(provider::mock::MockProvider::new(|symbol, start, _| {
+            if symbol == "FAIL" {
+                Err(Error::from(ErrorKind::TimedOut))
+            } else {
+                Ok(vec![series::tests::quote(
+                    start.timestamp().unsigned_abs(),
+                    1.0,
+                )])
+            }
+        }));
+        let symbols = vec!["AAPL".to_string(), "FAIL".to_string()];
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        );
+        let json = ReportSettings {
+            format: OutputFormat::Json,
+            ..settings()
+        };
+        let rendered = run_tick(&provider, &symbols, from, to, None, &json, None, None)
+            .await
+            .unwrap();
+        let rendered = String::from_utf8(rendered).unwrap();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"symbol\":\"AAPL\""));
+        assert_eq!(
+            lines[1],
+            r#"{"symbol":"FAIL","error":"timed out","kind":"timed_out"}"#
+        );
+
+        // arrays end with them
+        let array = ReportSettings {
+            style: Style {
+                json_array: true,
+                ..Style::default()
+            },
+            ..json
+        };
+        let rendered = run_tick(&provider, &symbols, from, to, None, &array, None, None)
+            .await
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&rendered).unwrap();
+        assert_eq!(parsed[0]["symbol"], "AAPL");
+        assert_eq!(parsed[1]["kind"], "timed_out");
+        assert_eq!(parsed.as_array().map(Vec::len), Some(2));
+        let failed = ["FAIL".to_string()];
+        let rendered = run_tick(&provider, &failed, from, to, None, &array, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(rendered).unwrap(),
+            "[{\"symbol\":\"FAIL\",\"error\":\"timed out\",\"kind\":\"timed_out\"}]\n"
+        );
+        let listed = ["AAPL".to_string(), "MSFT".to_string()];
+        let rendered = run_tick(&provider, &listed, from, to, None, &array, None, None)
+            .await
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&rendered).unwrap();
+        assert_eq!(parsed.as_array().map(Vec::len), Some(2));
+        assert_eq!(rendered.last(), Some(&b'\n'));
+
+        // other formats fail the whole tick
+        let err = run_tick(&provider, &symbols, from, to, None, &settings(), None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "FAIL: timed out");
+    }
+
+    #[tokio::test]
+    async fn test_run_symbols_report_shares_provider() {
+        let provider = Arc::new(provider::mock::MockProvider::daily());
+        let symbols = vec!["AAPL".to_string(), "MSFT".to_string()];
+        let (from, to) = (
+            Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+            Utc.ymd(2021, 1, 31).and_hms(0, 0, 0),
+        );
+        let settings = settings();
+        // two ticks
+        for _ in 0..2 {
+            let (reports, failures) =
+                run_symbols_report(&provider, symbols.clone(), from, to, &settings).await;
+            assert_eq!(reports.len(), 2);
+            assert!(failures.is_empty());
+        }
+        // every request of both ticks went to the one instance, which is
+        // only referenced here again once the tasks are done
+        assert_eq!(provider.call_count(), 4);
+        assert_eq!(Arc::strong_count(&provider), 1);
+    }
+
     #[test]
-    fn test_WindowedSMA_calculate() {
-        let series = vec![2.0, 4.5, 5.3, 6.5, 4.7];
+    fn test_process_series_warmup() {
+        let quotes = (0..40_u32)
+            .map(|i| series::tests::quote(1_609_459_200 + u64::from(i) * 86_400, f64::from(i)))
+            .collect();
+        let series = Series::from_quotes(quotes);
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let report = |warmup| {
+            let settings = ReportSettings {
+                series: true,
+                warmup,
+                ..settings()
+            };
+            process_series("AAPL", &series, &from, &settings).unwrap()
+        };
 
-        let signal = WindowedSMA { window_size: 3 };
+        let (cold, warm) = (report(0), report(5));
+        let (cold_series, warm_series) = (cold.sma_series.unwrap(), warm.sma_series.unwrap());
+        assert_eq!(cold_series.len(), 11);
+        assert_eq!(warm_series.len(), cold_series.len() - 5);
+        assert_eq!(warm_series[..], cold_series[5..]);
+        // the last value isn't affected
+        assert_approx_eq(warm.sma, cold.sma);
+        assert!(report(100).sma_series.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stdin_report() {
+        let closes = [10.0, 12.0, 11.0, 15.0, 14.0];
+        let input = "10\n12\n11\n\n15\n14\n";
+        let settings = ReportSettings {
+            sma_window: 3,
+            ..settings()
+        };
+        let report = stdin_report("TEST", input, &settings).unwrap().unwrap();
+        assert_eq!(report.symbol, "TEST");
+        assert_some_approx_eq(MaxPrice.calculate(&closes), report.period_max);
+        assert_some_approx_eq(MinPrice.calculate(&closes), report.period_min);
+        assert_approx_eq(closes[4], report.last_price);
+        let (_, change) = PriceDifference.calculate(&closes).unwrap();
+        assert_approx_eq(change * 100.0, report.pct_change);
+        let sma = WindowedSMA { window_size: 3 }.calculate(&closes).unwrap();
+        assert_some_approx_eq(sma.last().copied(), report.sma);
         assert_eq!(
-            signal.calculate(&series),
-            Some(vec![3.933_333_333_333_333_6, 5.433_333_333_333_334, 5.5])
+            report.period_start,
+            Utc::today().and_hms(0, 0, 0) - chrono::Duration::days(4)
         );
 
-        let signal = WindowedSMA { window_size: 5 };
-        assert_eq!(signal.calculate(&series), Some(vec![4.6]));
+        assert!(stdin_report("TEST", "", &settings).unwrap().is_none());
+        let err = stdin_report("TEST", "1\nx\n", &settings).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: 'x' is not a number");
+    }
+
+    #[test]
+    fn test_stdin_report_tail() {
+        let closes: Vec<_> = (1..=30).map(|i| f64::from(i * 7 % 31)).collect();
+        let input = closes
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let settings = ReportSettings {
+            sma_window: 5,
+            tail: Some(10),
+            ..settings()
+        };
+        let report = stdin_report("TEST", &input, &settings).unwrap().unwrap();
+        let last = &closes[20..];
+        assert_some_approx_eq(MaxPrice.calculate(last), report.period_max);
+        assert_some_approx_eq(MinPrice.calculate(last), report.period_min);
+        let (_, change) = PriceDifference.calculate(last).unwrap();
+        assert_approx_eq(change * 100.0, report.pct_change);
+        let sma = WindowedSMA { window_size: 5 }.calculate(last).unwrap();
+        assert_some_approx_eq(sma.last().copied(), report.sma);
+        // the full series has a different range
+        assert!(MinPrice.calculate(&closes) < MinPrice.calculate(last));
+
+        let opts = Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--tail", "0"]).unwrap();
+        let err = report_settings(&opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_report_settings_histogram() {
+        let opts =
+            Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--histogram", "0"]).unwrap();
+        let err = report_settings(&opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let opts =
+            Opts::try_parse_from(["ffl", "--from", "2021-01-01", "--histogram", "10"]).unwrap();
+        assert_eq!(report_settings(&opts).unwrap().histogram_bins, Some(10));
+    }
+
+    #[test]
+    fn test_report_settings_jitter() {
+        let parse = |args: &[&str]| {
+            Opts::try_parse_from(["ffl", "--from", "2021-01-01"].iter().chain(args))
+        };
+        // no jitter unless asked for
+        let opts = parse(&["--retries", "2"]).unwrap();
+        assert_eq!(report_settings(&opts).unwrap().jitter, None);
+        let opts = parse(&[
+            "--retries",
+            "2",
+            "--retry-jitter",
+            "0.5",
+            "--random-seed",
+            "7",
+        ])
+        .unwrap();
+        assert_eq!(
+            report_settings(&opts).unwrap().jitter,
+            Some(Jitter {
+                seed: 7,
+                fraction: 0.5
+            })
+        );
+        let opts = parse(&["--retries", "2", "--retry-jitter=-1"]).unwrap();
+        let err = report_settings(&opts).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(parse(&["--retries", "2", "--random-seed", "7"]).is_err());
+        // the default number of retries doesn't count
+        assert!(parse(&["--retry-jitter", "0.5"]).is_err());
+        assert!(parse(&["--retry-budget", "3"]).is_err());
+        assert_eq!(
+            parse(&["--retries", "2", "--retry-budget", "3"])
+                .unwrap()
+                .retry_budget,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_process_series_seed() {
+        let quotes = (0..5_u32)
+            .map(|i| series::tests::quote(1_609_459_200 + u64::from(i) * 86_400, f64::from(i + 10)))
+            .collect();
+        let series = Series::from_quotes(quotes);
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let seeds = HashMap::from([("AAPL".to_string(), vec![4.0, 6.0])]);
+        let settings = ReportSettings {
+            sma_window: 3,
+            series: true,
+            seed: Some(Arc::new(seeds)),
+            ..settings()
+        };
+        // only the symbol's own closes seed it
+        let unseeded = process_series("MSFT", &series, &from, &settings).unwrap();
+        assert_eq!(unseeded.sma_series.unwrap().len(), 3);
+        let report = process_series("AAPL", &series, &from, &settings).unwrap();
+        // the average of the first fetched close includes the seed
+        let sma_series = report.sma_series.unwrap();
+        assert_eq!(sma_series.len(), 5);
+        assert_eq!(sma_series[0].timestamp, series.timestamps[0]);
+        assert_approx_eq(sma_series[0].value, 20.0 / 3.0);
+        // everything else only covers the fetched closes
+        assert_approx_eq(report.period_min, 10.0);
+        assert_approx_eq(report.pct_change, 40.0);
+    }
+
+    #[test]
+    fn test_Backoff() {
+        let mut backoff = Backoff::new(Duration::from_secs(10), Duration::from_secs(100));
+        assert_eq!(backoff.delay(), Duration::from_secs(10));
+        assert_eq!(backoff.succeed(), Duration::from_secs(10));
+        assert_eq!(backoff.fail(), Duration::from_secs(20));
+        assert_eq!(backoff.fail(), Duration::from_secs(40));
+        assert_eq!(backoff.fail(), Duration::from_secs(80));
+        assert_eq!(backoff.fail(), Duration::from_secs(100));
+        assert_eq!(backoff.fail(), Duration::from_secs(100));
+        assert_eq!(backoff.succeed(), Duration::from_secs(10));
+        assert_eq!(backoff.fail(), Duration::from_secs(20));
+
+        for _ in 0..100 {
+            backoff.fail();
+        }
+        assert_eq!(backoff.delay(), Duration::from_secs(100));
 
-        let signal = WindowedSMA { window_size: 10 };
-        assert_eq!(signal.calculate(&series), Some(vec![]));
+        // a cap below the base interval never shortens it
+        let mut backoff = Backoff::new(Duration::from_secs(30), Duration::from_secs(10));
+        assert_eq!(backoff.fail(), Duration::from_secs(30));
     }
 }
@@ -0,0 +1,89 @@
+//!
+//! Trading hours of US exchanges in their local time, `America/New_York`.
+//!
+
+use chrono::prelude::*;
+
+///
+/// The regular session of US exchanges opens at 09:30 local time.
+///
+const OPEN: (u32, u32) = (9, 30);
+
+///
+/// The offset of New York from UTC at `instant`: Eastern Daylight Time from
+/// 02:00 on the second Sunday of March until 02:00 on the first Sunday of
+/// November, Eastern Standard Time otherwise.
+///
+pub fn new_york_offset(instant: DateTime<Utc>) -> FixedOffset {
+    let year = instant.year();
+    // the switches at 02:00 local are at 07:00 and 06:00 UTC
+    let dst_start = NaiveDate::from_weekday_of_month(year, 3, Weekday::Sun, 2).and_hms(7, 0, 0);
+    let dst_end = NaiveDate::from_weekday_of_month(year, 11, Weekday::Sun, 1).and_hms(6, 0, 0);
+    let instant = instant.naive_utc();
+    if instant >= dst_start && instant < dst_end {
+        FixedOffset::west(4 * 3600)
+    } else {
+        FixedOffset::west(5 * 3600)
+    }
+}
+
+///
+/// The most recent market open at or before `now`: today's in New York if
+/// it's a weekday past 09:30, otherwise that of the last weekday before.
+/// Holidays aren't accounted for.
+///
+pub fn last_open(now: DateTime<Utc>) -> DateTime<Utc> {
+    let local = now.with_timezone(&new_york_offset(now));
+    let mut day = local.date().naive_local();
+    if local.time() < NaiveTime::from_hms(OPEN.0, OPEN.1, 0) {
+        day = day.pred();
+    }
+    while day.weekday().number_from_monday() > 5 {
+        day = day.pred();
+    }
+    let open = day.and_hms(OPEN.0, OPEN.1, 0);
+    // the offset at noon UTC of that day is the one at its open
+    let offset = new_york_offset(Utc.from_utc_datetime(&day.and_hms(12, 0, 0)));
+    Utc.from_utc_datetime(&(open - offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_york_offset() {
+        let offset = |instant: DateTime<Utc>| new_york_offset(instant).utc_minus_local() / 3600;
+        assert_eq!(offset(Utc.ymd(2021, 1, 15).and_hms(12, 0, 0)), 5);
+        assert_eq!(offset(Utc.ymd(2021, 7, 15).and_hms(12, 0, 0)), 4);
+        // 2021 switched on March 14th and November 7th
+        assert_eq!(offset(Utc.ymd(2021, 3, 14).and_hms(6, 59, 59)), 5);
+        assert_eq!(offset(Utc.ymd(2021, 3, 14).and_hms(7, 0, 0)), 4);
+        assert_eq!(offset(Utc.ymd(2021, 11, 7).and_hms(5, 59, 59)), 4);
+        assert_eq!(offset(Utc.ymd(2021, 11, 7).and_hms(6, 0, 0)), 5);
+    }
+
+    #[test]
+    fn test_last_open() {
+        // Wednesday, 2021-11-03, 11:00 in New York (EDT)
+        let wednesday = Utc.ymd(2021, 11, 3).and_hms(15, 0, 0);
+        assert_eq!(
+            last_open(wednesday),
+            Utc.ymd(2021, 11, 3).and_hms(13, 30, 0)
+        );
+        // before the open it's the previous day's
+        let early = Utc.ymd(2021, 11, 3).and_hms(13, 0, 0);
+        assert_eq!(last_open(early), Utc.ymd(2021, 11, 2).and_hms(13, 30, 0));
+        // Sunday, 2021-11-07 after the switch to EST rolls back to Friday,
+        // which was still on EDT
+        let sunday = Utc.ymd(2021, 11, 7).and_hms(18, 0, 0);
+        assert_eq!(last_open(sunday), Utc.ymd(2021, 11, 5).and_hms(13, 30, 0));
+        // Monday before the open in EST rolls back to Friday as well
+        let monday = Utc.ymd(2021, 11, 8).and_hms(14, 0, 0);
+        assert_eq!(last_open(monday), Utc.ymd(2021, 11, 5).and_hms(13, 30, 0));
+        assert_eq!(
+            last_open(Utc.ymd(2021, 11, 8).and_hms(14, 30, 0)),
+            Utc.ymd(2021, 11, 8).and_hms(14, 30, 0)
+        );
+    }
+}
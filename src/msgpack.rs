@@ -0,0 +1,47 @@
+//!
+//! A `MessagePack` encoding of rows for compact binary logs, framed per tick.
+//!
+
+use serde_json::Value;
+
+///
+/// A tick's frame: the length of the payload as a big-endian `u32` followed
+/// by the payload, an array of the rows as maps. The length lets consumers
+/// decode a stream of ticks one frame at a time.
+///
+pub fn frame(rows: &[Value]) -> Vec<u8> {
+    let payload = rmp_serde::to_vec(rows).expect("JSON values encode as MessagePack");
+    let len = u32::try_from(payload.len()).expect("a tick's frame fits into 4 GiB");
+    let mut frame = len.to_be_bytes().to_vec();
+    frame.extend(payload);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{tests::reports, OutputFormat, Style};
+    use crate::report::SymbolReport;
+
+    #[test]
+    fn test_frame() {
+        let mut reports = reports();
+        reports[0].external = Some(vec![1.0, -3.0]);
+        let encoded =
+            crate::output::render_bytes(OutputFormat::Msgpack, &Style::default(), &reports);
+        // two ticks in a stream decode one frame at a time
+        let stream = [encoded.clone(), encoded].concat();
+        let mut input = &stream[..];
+        for _ in 0..2 {
+            let (len, rest) = input.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+            let (payload, rest) = rest.split_at(len);
+            input = rest;
+            let decoded: Vec<SymbolReport> = rmp_serde::from_slice(payload).unwrap();
+            assert_eq!(decoded, reports);
+        }
+        assert!(input.is_empty());
+        // an array of two maps, with the keys of the rows
+        assert_eq!(stream[4], 0x92);
+        assert_eq!(stream[5] & 0xf0, 0x80);
+    }
+}
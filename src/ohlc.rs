@@ -0,0 +1,1078 @@
+//!
+//! Signals calculated on bars of open, high, low, close and volume.
+//!
+
+use crate::signals::{AsyncStockSignal, PercentagePriceOscillator, WindowedSMA};
+
+///
+/// A single period's prices and traded volume. Unlike the closing series
+/// these prices aren't adjusted for splits and dividends.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+}
+
+impl Bar {
+    ///
+    /// The average of high, low and close.
+    ///
+    #[allow(dead_code)]
+    pub fn typical_price(&self) -> f64 {
+        (self.high + self.low + self.close) / 3.0
+    }
+}
+
+///
+/// A trait to provide a common interface for all signal calculations on bars.
+///
+pub trait AsyncBarSignal {
+    ///
+    /// The signal's data type.
+    ///
+    type SignalType;
+
+    ///
+    /// Calculate the signal on the provided bars.
+    ///
+    /// # Returns
+    ///
+    /// The signal (using the provided type) or `None` on error/invalid data.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType>;
+}
+
+///
+/// The true range of each bar after the first: the largest of its high-low
+/// range and the distances of its high and low from the previous close.
+///
+#[allow(dead_code)]
+pub fn true_ranges(bars: &[Bar]) -> Vec<f64> {
+    bars.windows(2)
+        .map(|w| {
+            let (previous, bar) = (w[0].close, w[1]);
+            (bar.high - bar.low)
+                .max((bar.high - previous).abs())
+                .max((bar.low - previous).abs())
+        })
+        .collect()
+}
+
+#[allow(dead_code)]
+pub struct AverageTrueRange {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct NormalizedAtr {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct GapCounts {
+    pub threshold_pct: f64,
+}
+#[allow(dead_code)]
+pub struct MoneyFlowIndex {
+    pub period: usize,
+}
+pub struct AverageVolume;
+#[allow(dead_code)]
+pub struct ChaikinMoneyFlow {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct ChoppinessIndex {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct VortexIndicator {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct ElderRay {
+    pub ema_period: usize,
+}
+#[allow(dead_code)]
+pub struct BalanceOfPower;
+#[allow(dead_code)]
+pub struct GannHiLo {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct KlingerOscillator {
+    pub fast: usize,
+    pub slow: usize,
+    pub signal: usize,
+}
+#[allow(dead_code)]
+pub struct EaseOfMovement {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct PercentageVolumeOscillator {
+    pub fast: usize,
+    pub slow: usize,
+    pub signal: usize,
+}
+#[allow(dead_code)]
+pub struct MassIndex {
+    pub ema_period: usize,
+    pub sum_period: usize,
+}
+
+impl AsyncBarSignal for AverageTrueRange {
+    type SignalType = f64;
+
+    ///
+    /// The mean of the last `period` true ranges.
+    ///
+    /// # Returns
+    ///
+    /// The average or `None` with fewer than `period + 1` bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() <= self.period {
+            return None;
+        }
+        let ranges = true_ranges(&bars[bars.len() - self.period - 1..]);
+        #[allow(clippy::cast_precision_loss)]
+        Some(ranges.iter().sum::<f64>() / self.period as f64)
+    }
+}
+
+impl AsyncBarSignal for NormalizedAtr {
+    type SignalType = f64;
+
+    ///
+    /// The average true range as a percentage of the last close (ATR%), which
+    /// makes volatility comparable across symbols of different price levels.
+    ///
+    /// # Returns
+    ///
+    /// The percentage or `None` with insufficient bars or a zero last close.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let atr = AverageTrueRange {
+            period: self.period,
+        }
+        .calculate(bars)?;
+        let last = bars.last()?.close;
+        if last == 0.0 {
+            return None;
+        }
+        Some(atr / last * 100.0)
+    }
+}
+
+impl AsyncBarSignal for GapCounts {
+    type SignalType = (usize, usize);
+
+    ///
+    /// Count the bars opening more than `threshold_pct` percent above (gap up)
+    /// or below (gap down) the previous close. A gap of exactly the threshold
+    /// doesn't count.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(gap_ups, gap_downs)` or `None` with fewer than two bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.len() < 2 {
+            return None;
+        }
+        Some(bars.windows(2).fold((0, 0), |(ups, downs), w| {
+            let (previous, open) = (w[0].close, w[1].open);
+            let gap = previous.abs() * self.threshold_pct / 100.0;
+            if open - previous > gap {
+                (ups + 1, downs)
+            } else if previous - open > gap {
+                (ups, downs + 1)
+            } else {
+                (ups, downs)
+            }
+        }))
+    }
+}
+
+impl AsyncBarSignal for MoneyFlowIndex {
+    type SignalType = f64;
+
+    ///
+    /// The money flow index over the last `period` bars: the money flow
+    /// (typical price × volume) of bars whose typical price rose is positive,
+    /// of those where it fell negative, and
+    /// `MFI = 100 - 100 / (1 + positive / negative)`.
+    ///
+    /// # Returns
+    ///
+    /// The index between 0 and 100, `100` without negative flow (`50` without
+    /// any flow), or `None` with fewer than `period + 1` bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() <= self.period {
+            return None;
+        }
+        let (positive, negative) = bars[bars.len() - self.period - 1..].windows(2).fold(
+            (0.0, 0.0),
+            |(positive, negative), w| {
+                let (previous, current) = (w[0].typical_price(), w[1].typical_price());
+                #[allow(clippy::cast_precision_loss)]
+                let flow = current * w[1].volume as f64;
+                if current > previous {
+                    (positive + flow, negative)
+                } else if current < previous {
+                    (positive, negative + flow)
+                } else {
+                    (positive, negative)
+                }
+            },
+        );
+        if negative == 0.0 {
+            return Some(if positive == 0.0 { 50.0 } else { 100.0 });
+        }
+        Some(100.0 - 100.0 / (1.0 + positive / negative))
+    }
+}
+
+impl AsyncBarSignal for AverageVolume {
+    type SignalType = f64;
+
+    ///
+    /// The mean traded volume per bar.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.is_empty() {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        Some(bars.iter().map(|b| b.volume as f64).sum::<f64>() / bars.len() as f64)
+    }
+}
+
+impl AsyncBarSignal for ChaikinMoneyFlow {
+    type SignalType = f64;
+
+    ///
+    /// The Chaikin money flow over the last `period` bars: the sum of each
+    /// bar's money flow volume (close location value × volume) over the sum
+    /// of their volume, where
+    /// `CLV = ((close - low) - (high - close)) / (high - low)` and `0` for
+    /// bars without a range.
+    ///
+    /// # Returns
+    ///
+    /// The flow between -1 and 1 or `None` with fewer than `period` bars or
+    /// without any volume.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() < self.period {
+            return None;
+        }
+        let (flow, volume) =
+            bars[bars.len() - self.period..]
+                .iter()
+                .fold((0.0, 0.0), |(flow, volume), bar| {
+                    let range = bar.high - bar.low;
+                    let clv = if range == 0.0 {
+                        0.0
+                    } else {
+                        ((bar.close - bar.low) - (bar.high - bar.close)) / range
+                    };
+                    #[allow(clippy::cast_precision_loss)]
+                    let bar_volume = bar.volume as f64;
+                    (flow + clv * bar_volume, volume + bar_volume)
+                });
+        if volume == 0.0 {
+            return None;
+        }
+        Some(flow / volume)
+    }
+}
+
+impl AsyncBarSignal for ChoppinessIndex {
+    type SignalType = f64;
+
+    ///
+    /// The choppiness index over the last `period` bars, comparing the sum of
+    /// their true ranges to the range they span:
+    /// `CI = 100 * log10(sum(TR) / (max(high) - min(low))) / log10(period)`.
+    /// Low values indicate a trend, high values a consolidation.
+    ///
+    /// # Returns
+    ///
+    /// The index between 0 and 100 or `None` with a `period` below two,
+    /// fewer than `period + 1` bars or bars that span no range.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period < 2 || bars.len() <= self.period {
+            return None;
+        }
+        let window = &bars[bars.len() - self.period - 1..];
+        let sum: f64 = true_ranges(window).iter().sum();
+        let (high, low) = window[1..]
+            .iter()
+            .fold((f64::MIN, f64::MAX), |(high, low), bar| {
+                (high.max(bar.high), low.min(bar.low))
+            });
+        if high <= low {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        Some(100.0 * (sum / (high - low)).log10() / (self.period as f64).log10())
+    }
+}
+
+impl AsyncBarSignal for VortexIndicator {
+    type SignalType = (f64, f64);
+
+    ///
+    /// The vortex indicator over the last `period` bars: the sums of the
+    /// positive movements `|high - previous low|` and the negative movements
+    /// `|low - previous high|`, each divided by the sum of the true ranges.
+    /// VI+ crossing above VI- marks the start of an uptrend and vice versa.
+    ///
+    /// # Returns
+    ///
+    /// `(vi_plus, vi_minus)` or `None` with a `period` of zero, fewer than
+    /// `period + 1` bars or bars that span no range.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.period == 0 || bars.len() <= self.period {
+            return None;
+        }
+        let window = &bars[bars.len() - self.period - 1..];
+        let range: f64 = true_ranges(window).iter().sum();
+        if range <= 0.0 {
+            return None;
+        }
+        let (plus, minus) = window.windows(2).fold((0.0, 0.0), |(plus, minus), w| {
+            (
+                plus + (w[1].high - w[0].low).abs(),
+                minus + (w[1].low - w[0].high).abs(),
+            )
+        });
+        Some((plus / range, minus / range))
+    }
+}
+
+impl AsyncBarSignal for ElderRay {
+    type SignalType = (f64, f64);
+
+    ///
+    /// Elder's bull and bear power of the latest bar: how far its high and
+    /// its low are from the `ema_period` EMA of the closes. Positive bear
+    /// power means even the low held above the trend.
+    ///
+    /// # Returns
+    ///
+    /// `(bull_power, bear_power)` or `None` with fewer than `ema_period`
+    /// bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+        let ema = *crate::signals::ema(&closes, self.ema_period)?.last()?;
+        let latest = bars.last()?;
+        Some((latest.high - ema, latest.low - ema))
+    }
+}
+
+impl AsyncBarSignal for MassIndex {
+    type SignalType = f64;
+
+    ///
+    /// The Mass Index: the sum over the last `sum_period` bars of the ratio
+    /// of the `ema_period` EMA of the high-low range to the EMA of that EMA.
+    /// It rises as the range widens, a "reversal bulge" above 27 that falls
+    /// back below 26.5 hints at a turn of the trend.
+    ///
+    /// # Returns
+    ///
+    /// The index or `None` with a period of zero, fewer than
+    /// `2 * ema_period + sum_period - 2` bars or a zero double EMA.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if self.sum_period == 0 {
+            return None;
+        }
+        let ranges: Vec<f64> = bars.iter().map(|bar| bar.high - bar.low).collect();
+        let single = crate::signals::ema(&ranges, self.ema_period)?;
+        let double = crate::signals::ema(&single, self.ema_period)?;
+        if double.len() < self.sum_period {
+            return None;
+        }
+        single[single.len() - self.sum_period..]
+            .iter()
+            .zip(&double[double.len() - self.sum_period..])
+            .map(|(single, double)| (double.abs() > 1e-12).then(|| single / double))
+            .sum()
+    }
+}
+
+impl AsyncBarSignal for BalanceOfPower {
+    type SignalType = f64;
+
+    ///
+    /// The balance of power of the latest bar: how far it closed from its
+    /// open relative to its range, from -1 when sellers drove it from the high
+    /// all the way to the low to 1 for the opposite. A bar without a range is
+    /// balanced at 0.
+    ///
+    /// # Returns
+    ///
+    /// The balance or `None` without bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let bar = bars.last()?;
+        let range = bar.high - bar.low;
+        if range <= 0.0 {
+            return Some(0.0);
+        }
+        Some(((bar.close - bar.open) / range).clamp(-1.0, 1.0))
+    }
+}
+
+impl AsyncBarSignal for GannHiLo {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The Gann Hi-Lo activator: the `period` SMA of the lows while the trend
+    /// is up and of the highs while it's down, a trailing stop under or over
+    /// the price. The trend turns up when a close rises above the SMA of the
+    /// highs and down when one falls below the SMA of the lows, and starts on
+    /// the side of their midpoint the first close is on.
+    ///
+    /// # Returns
+    ///
+    /// The activator for each bar with `period` bars up to it or `None` with
+    /// a `period` below two or fewer bars.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.len() < self.period {
+            return None;
+        }
+        let sma = WindowedSMA {
+            window_size: self.period,
+        };
+        let highs = sma.calculate(&bars.iter().map(|bar| bar.high).collect::<Vec<_>>())?;
+        let lows = sma.calculate(&bars.iter().map(|bar| bar.low).collect::<Vec<_>>())?;
+        let mut up: Option<bool> = None;
+        Some(
+            bars[self.period - 1..]
+                .iter()
+                .zip(highs.iter().zip(&lows))
+                .map(|(bar, (high, low))| {
+                    let trend = if bar.close > *high {
+                        true
+                    } else if bar.close < *low {
+                        false
+                    } else {
+                        up.unwrap_or(bar.close >= (high + low) / 2.0)
+                    };
+                    up = Some(trend);
+                    if trend {
+                        *low
+                    } else {
+                        *high
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+///
+/// Klinger's volume force of each bar after the first: its volume signed by
+/// the trend, whether the sum of high, low and close rose, and scaled by
+/// `|2 * (dm / cm - 1)| * 100`. `dm` is the bar's range and `cm` the sum of
+/// the ranges since the trend last turned, including the bar before the turn.
+///
+#[allow(dead_code)]
+pub fn volume_force(bars: &[Bar]) -> Vec<f64> {
+    let mut trend: Option<bool> = None;
+    let mut cm = 0.0;
+    bars.windows(2)
+        .map(|w| {
+            let (previous, bar) = (w[0], w[1]);
+            let up = bar.high + bar.low + bar.close > previous.high + previous.low + previous.close;
+            let dm = bar.high - bar.low;
+            cm = if trend == Some(up) {
+                cm + dm
+            } else {
+                previous.high - previous.low + dm
+            };
+            trend = Some(up);
+            let scale = if cm > 0.0 {
+                (2.0 * (dm / cm - 1.0)).abs()
+            } else {
+                0.0
+            };
+            #[allow(clippy::cast_precision_loss)]
+            let volume = bar.volume as f64;
+            let force = volume * scale * 100.0;
+            if up {
+                force
+            } else {
+                -force
+            }
+        })
+        .collect()
+}
+
+impl AsyncBarSignal for KlingerOscillator {
+    type SignalType = (f64, f64);
+
+    ///
+    /// The Klinger volume oscillator of the latest bar: the difference of
+    /// the `fast` and `slow` EMAs of the volume force, and its `signal` EMA.
+    /// Divergences from the price hint at the trend running out of volume.
+    ///
+    /// # Returns
+    ///
+    /// `(oscillator, signal_line)` or `None` without any volume or with
+    /// too few bars for the EMAs.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.iter().all(|bar| bar.volume == 0) {
+            return None;
+        }
+        let force = volume_force(bars);
+        let fast = crate::signals::ema(&force, self.fast)?;
+        let slow = crate::signals::ema(&force, self.slow)?;
+        let len = fast.len().min(slow.len());
+        let oscillator: Vec<f64> = fast[fast.len() - len..]
+            .iter()
+            .zip(&slow[slow.len() - len..])
+            .map(|(fast, slow)| fast - slow)
+            .collect();
+        let signal = crate::signals::ema(&oscillator, self.signal)?;
+        Some((*oscillator.last()?, *signal.last()?))
+    }
+}
+
+impl AsyncBarSignal for EaseOfMovement {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Arms' ease of movement: how far the midpoint of each bar moved from
+    /// the previous one, divided by the box ratio of its volume over its
+    /// range, averaged over `period` bars. High values mean the price moved
+    /// easily on little volume.
+    ///
+    /// # Returns
+    ///
+    /// The average for each bar from the first full window or `None` with a
+    /// `period` below two, no more bars than `period` or a bar without range
+    /// or volume.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        let midpoint = |bar: &Bar| f64::midpoint(bar.high, bar.low);
+        let emv = bars
+            .windows(2)
+            .map(|w| {
+                let range = w[1].high - w[1].low;
+                if range <= 0.0 || w[1].volume == 0 {
+                    return None;
+                }
+                #[allow(clippy::cast_precision_loss)]
+                let box_ratio = w[1].volume as f64 / range;
+                Some((midpoint(&w[1]) - midpoint(&w[0])) / box_ratio)
+            })
+            .collect::<Option<Vec<f64>>>()?;
+        let smoothed = WindowedSMA {
+            window_size: self.period,
+        }
+        .calculate(&emv)?;
+        (!smoothed.is_empty()).then_some(smoothed)
+    }
+}
+
+impl AsyncBarSignal for PercentageVolumeOscillator {
+    type SignalType = Vec<(f64, f64, f64)>;
+
+    ///
+    /// The percentage price oscillator applied to the volume: the difference
+    /// of a `fast` and a `slow` EMA of the volume as a percentage of the slow
+    /// one, with a `signal` EMA of it and their difference. Positive while
+    /// the volume is above its longer average, e.g. after a surge.
+    ///
+    /// # Returns
+    ///
+    /// `(pvo, signal_line, histogram)` for each bar the signal line covers,
+    /// or `None` without any volume, with too few bars for the EMAs or if the
+    /// slow one is zero.
+    ///
+    fn calculate(&self, bars: &[Bar]) -> Option<Self::SignalType> {
+        if bars.iter().all(|bar| bar.volume == 0) {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let volumes: Vec<f64> = bars.iter().map(|bar| bar.volume as f64).collect();
+        PercentagePriceOscillator {
+            fast: self.fast,
+            slow: self.slow,
+            signal: self.signal,
+        }
+        .calculate(&volumes)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::approx::{
+        assert_all_approx_eq, assert_approx_eq, assert_pair_approx_eq, assert_some_approx_eq,
+    };
+
+    ///
+    /// A bar with the open at the close.
+    ///
+    pub fn bar(high: f64, low: f64, close: f64, volume: u64) -> Bar {
+        Bar {
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+        }
+    }
+
+    fn scaled(bars: &[Bar], factor: f64) -> Vec<Bar> {
+        bars.iter()
+            .map(|b| Bar {
+                open: b.open * factor,
+                high: b.high * factor,
+                low: b.low * factor,
+                close: b.close * factor,
+                volume: b.volume,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_true_ranges() {
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            // gap up: distance between high and previous close
+            bar(14.0, 12.0, 13.0, 100),
+            // gap down: distance between low and previous close
+            bar(9.0, 7.0, 8.0, 100),
+            // inside bar: high-low range
+            bar(10.0, 6.0, 9.0, 100),
+        ];
+        assert_eq!(true_ranges(&bars), vec![5.0, 6.0, 4.0]);
+        assert!(true_ranges(&bars[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_AverageTrueRange_calculate() {
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(14.0, 12.0, 13.0, 100),
+            bar(9.0, 7.0, 8.0, 100),
+            bar(10.0, 6.0, 9.0, 100),
+        ];
+        assert_some_approx_eq(AverageTrueRange { period: 3 }.calculate(&bars), 5.0);
+        assert_some_approx_eq(AverageTrueRange { period: 2 }.calculate(&bars), 5.0);
+        assert_eq!(AverageTrueRange { period: 4 }.calculate(&bars), None);
+        assert_eq!(AverageTrueRange { period: 0 }.calculate(&bars), None);
+    }
+
+    #[test]
+    fn test_NormalizedAtr_calculate() {
+        let cheap = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(14.0, 12.0, 13.0, 100),
+            bar(9.0, 7.0, 8.0, 100),
+            bar(10.0, 6.0, 10.0, 100),
+        ];
+        let expensive = scaled(&cheap, 25.0);
+        let signal = NormalizedAtr { period: 3 };
+
+        let cheap_atr = AverageTrueRange { period: 3 }.calculate(&cheap).unwrap();
+        let expensive_atr = AverageTrueRange { period: 3 }
+            .calculate(&expensive)
+            .unwrap();
+        assert_approx_eq(expensive_atr, cheap_atr * 25.0);
+
+        let cheap_pct = signal.calculate(&cheap).unwrap();
+        assert_approx_eq(cheap_pct, 50.0);
+        assert_some_approx_eq(signal.calculate(&expensive), cheap_pct);
+
+        let mut zero_close = cheap;
+        zero_close[3].close = 0.0;
+        assert_eq!(signal.calculate(&zero_close), None);
+        assert_eq!(signal.calculate(&cheap[..3]), None);
+    }
+
+    fn opening(open: f64, close: f64) -> Bar {
+        Bar {
+            open,
+            high: open.max(close),
+            low: open.min(close),
+            close,
+            volume: 100,
+        }
+    }
+
+    #[test]
+    fn test_GapCounts_calculate() {
+        let bars = [
+            opening(100.0, 100.0),
+            // 5% gap up
+            opening(105.0, 100.0),
+            // exactly 2% gap down, not counted
+            opening(98.0, 100.0),
+            // 1% gap up, below the threshold
+            opening(101.0, 100.0),
+            // 3% gap down
+            opening(97.0, 97.0),
+            // 2.5% gap up
+            opening(99.425, 99.0),
+        ];
+        let signal = GapCounts { threshold_pct: 2.0 };
+        assert_eq!(signal.calculate(&bars), Some((2, 1)));
+        assert_eq!(
+            GapCounts { threshold_pct: 0.0 }.calculate(&bars),
+            Some((3, 2))
+        );
+        assert_eq!(
+            GapCounts {
+                threshold_pct: 10.0
+            }
+            .calculate(&bars),
+            Some((0, 0))
+        );
+
+        assert_eq!(signal.calculate(&bars[..1]), None);
+        assert_eq!(signal.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_MoneyFlowIndex_calculate() {
+        // typical prices 9, 10, 9, 11 with flows +2000, -900, +3300
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(11.0, 9.0, 10.0, 200),
+            bar(10.0, 8.0, 9.0, 100),
+            bar(12.0, 10.0, 11.0, 300),
+        ];
+        let mfi = MoneyFlowIndex { period: 3 }.calculate(&bars).unwrap();
+        assert_approx_eq(mfi, 100.0 - 100.0 / (1.0 + 5300.0 / 900.0));
+        assert_approx_eq(mfi, 85.483_870_967_741_94);
+
+        // only the last `period` changes count
+        let mfi = MoneyFlowIndex { period: 1 }.calculate(&bars).unwrap();
+        assert_approx_eq(mfi, 100.0);
+
+        assert_eq!(MoneyFlowIndex { period: 4 }.calculate(&bars), None);
+        assert_eq!(MoneyFlowIndex { period: 0 }.calculate(&bars), None);
+        assert_eq!(MoneyFlowIndex { period: 3 }.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_MoneyFlowIndex_no_negative_flow() {
+        let rising = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(11.0, 9.0, 10.0, 200),
+            bar(12.0, 10.0, 11.0, 300),
+        ];
+        assert_eq!(MoneyFlowIndex { period: 2 }.calculate(&rising), Some(100.0));
+
+        let flat = [bar(10.0, 8.0, 9.0, 100), bar(10.0, 8.0, 9.0, 100)];
+        assert_eq!(MoneyFlowIndex { period: 1 }.calculate(&flat), Some(50.0));
+    }
+
+    #[test]
+    fn test_AverageVolume_calculate() {
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(11.0, 9.0, 10.0, 200),
+            bar(12.0, 10.0, 11.0, 600),
+        ];
+        assert_eq!(AverageVolume.calculate(&bars), Some(300.0));
+        assert_eq!(AverageVolume.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_ChaikinMoneyFlow_calculate() {
+        // close location values are 0.5, -0.5 and 1
+        let bars = [
+            bar(10.0, 8.0, 9.5, 100),
+            bar(11.0, 9.0, 9.5, 200),
+            bar(12.0, 10.0, 12.0, 300),
+        ];
+        let cmf = ChaikinMoneyFlow { period: 3 }.calculate(&bars).unwrap();
+        assert_approx_eq(cmf, (50.0 - 100.0 + 300.0) / 600.0);
+        let cmf = ChaikinMoneyFlow { period: 2 }.calculate(&bars).unwrap();
+        assert_approx_eq(cmf, 200.0 / 500.0);
+
+        assert_eq!(ChaikinMoneyFlow { period: 4 }.calculate(&bars), None);
+        assert_eq!(ChaikinMoneyFlow { period: 0 }.calculate(&bars), None);
+    }
+
+    #[test]
+    fn test_ChaikinMoneyFlow_zero_volume() {
+        let bars = [bar(10.0, 8.0, 9.5, 0), bar(11.0, 9.0, 9.5, 0)];
+        assert_eq!(ChaikinMoneyFlow { period: 2 }.calculate(&bars), None);
+
+        // bars without a range don't move the flow
+        let flat = [bar(10.0, 10.0, 10.0, 100), bar(12.0, 10.0, 12.0, 100)];
+        let cmf = ChaikinMoneyFlow { period: 2 }.calculate(&flat).unwrap();
+        assert_approx_eq(cmf, 0.5);
+    }
+
+    #[test]
+    fn test_ChoppinessIndex_calculate() {
+        let signal = ChoppinessIndex { period: 4 };
+        // every bar extends the range by its true range
+        let trending: Vec<_> = (0..6)
+            .map(|i| {
+                let low = f64::from(i);
+                bar(low + 1.0, low, low + 1.0, 100)
+            })
+            .collect();
+        assert_some_approx_eq(signal.calculate(&trending), 0.0);
+
+        // every bar spans the whole range
+        let ranging: Vec<_> = (0..6)
+            .map(|i| bar(11.0, 9.0, if i % 2 == 0 { 9.0 } else { 11.0 }, 100))
+            .collect();
+        assert_some_approx_eq(signal.calculate(&ranging), 100.0);
+
+        assert_eq!(signal.calculate(&ranging[..4]), None);
+        assert_eq!(ChoppinessIndex { period: 1 }.calculate(&ranging), None);
+        let flat = [bar(10.0, 10.0, 10.0, 100); 5];
+        assert_eq!(signal.calculate(&flat), None);
+    }
+
+    #[test]
+    fn test_VortexIndicator_calculate() {
+        let signal = VortexIndicator { period: 2 };
+        let bars = [
+            bar(7.0, 5.0, 6.0, 100),
+            bar(10.0, 8.0, 9.0, 100),
+            // VM+ 4, VM- 1, TR 3
+            bar(12.0, 9.0, 11.0, 100),
+            // VM+ 4, VM- 2, TR 3
+            bar(13.0, 10.0, 12.0, 100),
+        ];
+        assert_pair_approx_eq(signal.calculate(&bars), (8.0 / 6.0, 0.5));
+        // a falling market swaps the movements
+        let falling: Vec<_> = bars
+            .iter()
+            .rev()
+            .map(|b| bar(b.high, b.low, b.low, 100))
+            .collect();
+        let (plus, minus) = signal.calculate(&falling).unwrap();
+        assert!(plus < minus);
+
+        assert_eq!(signal.calculate(&bars[..2]), None);
+        assert_eq!(VortexIndicator { period: 0 }.calculate(&bars), None);
+        let flat = [bar(10.0, 10.0, 10.0, 100); 3];
+        assert_eq!(signal.calculate(&flat), None);
+    }
+
+    #[test]
+    fn test_ElderRay_calculate() {
+        let signal = ElderRay { ema_period: 3 };
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(12.0, 10.0, 11.0, 100),
+            bar(13.0, 11.0, 12.0, 100),
+            bar(15.0, 13.0, 14.0, 100),
+        ];
+        // the EMA is seeded with 32/3 and moves halfway to 14: 37/3
+        assert_pair_approx_eq(signal.calculate(&bars), (8.0 / 3.0, 2.0 / 3.0));
+        // the seed alone
+        assert_pair_approx_eq(signal.calculate(&bars[..3]), (7.0 / 3.0, 1.0 / 3.0));
+        assert_eq!(signal.calculate(&bars[..2]), None);
+        assert_eq!(ElderRay { ema_period: 0 }.calculate(&bars), None);
+    }
+
+    #[test]
+    fn test_MassIndex_calculate() {
+        let signal = MassIndex {
+            ema_period: 2,
+            sum_period: 2,
+        };
+        // ranges 2, 4, 2, 4, 2
+        let bars = [
+            bar(11.0, 9.0, 10.0, 100),
+            bar(12.0, 8.0, 10.0, 100),
+            bar(11.0, 9.0, 10.0, 100),
+            bar(12.0, 8.0, 10.0, 100),
+            bar(11.0, 9.0, 10.0, 100),
+        ];
+        // single EMA 3, 7/3, 31/9, 67/27 and double EMA 8/3, 86/27, 220/81
+        let mass = signal.calculate(&bars).unwrap();
+        assert_approx_eq(mass, 93.0 / 86.0 + 201.0 / 220.0);
+        let mass = signal.calculate(&bars[..4]).unwrap();
+        assert_approx_eq(mass, 7.0 / 8.0 + 93.0 / 86.0);
+
+        assert_eq!(signal.calculate(&bars[..3]), None);
+        assert_eq!(
+            MassIndex {
+                ema_period: 0,
+                sum_period: 2
+            }
+            .calculate(&bars),
+            None
+        );
+        let flat = [bar(10.0, 10.0, 10.0, 100); 5];
+        assert_eq!(signal.calculate(&flat), None);
+    }
+
+    #[test]
+    fn test_BalanceOfPower_calculate() {
+        // buyers took it from the low to the high
+        let buyers = [bar(9.0, 8.0, 8.5, 100), opening(10.0, 12.0)];
+        assert_eq!(BalanceOfPower.calculate(&buyers), Some(1.0));
+        // sellers did the opposite
+        assert_eq!(BalanceOfPower.calculate(&[opening(12.0, 10.0)]), Some(-1.0));
+        // the close is half the range above the open
+        let partial = Bar {
+            open: 10.0,
+            high: 13.0,
+            low: 9.0,
+            close: 12.0,
+            volume: 100,
+        };
+        assert_eq!(BalanceOfPower.calculate(&[partial]), Some(0.5));
+        // a doji without a range
+        assert_eq!(
+            BalanceOfPower.calculate(&[bar(10.0, 10.0, 10.0, 100)]),
+            Some(0.0)
+        );
+        assert_eq!(BalanceOfPower.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_GannHiLo_calculate() {
+        let signal = GannHiLo { period: 2 };
+        let mut bars = vec![
+            bar(11.0, 9.0, 10.0, 100),
+            // above the midpoint 10.5 of the SMAs, trailing the lows
+            bar(12.0, 10.0, 11.0, 100),
+            bar(13.0, 11.0, 12.5, 100),
+            // touching the SMA of the lows doesn't flip it
+            bar(12.0, 10.0, 10.5, 100),
+            // falling below 9 flips it to the highs
+            bar(10.0, 8.0, 8.5, 100),
+            bar(9.0, 7.0, 7.5, 100),
+        ];
+        assert_eq!(
+            signal.calculate(&bars),
+            Some(vec![9.5, 10.5, 10.5, 11.0, 9.5])
+        );
+        // rising above the SMA of the highs flips it back
+        bars.push(bar(12.0, 10.0, 11.5, 100));
+        assert_eq!(signal.calculate(&bars).unwrap().last(), Some(&8.5));
+
+        assert_eq!(signal.calculate(&bars[..1]), None);
+        assert_eq!(GannHiLo { period: 1 }.calculate(&bars), None);
+    }
+
+    #[test]
+    fn test_KlingerOscillator_calculate() {
+        let signal = KlingerOscillator {
+            fast: 2,
+            slow: 3,
+            signal: 2,
+        };
+        let bars = [
+            bar(11.0, 9.0, 10.0, 100),
+            // up, dm 3 and cm 2 + 3
+            bar(13.0, 10.0, 12.0, 200),
+            // still up, cm 8
+            bar(14.0, 11.0, 13.0, 100),
+            // turning down, cm 3 + 3
+            bar(12.0, 9.0, 10.0, 300),
+            // turning up, cm 3 + 4
+            bar(15.0, 11.0, 14.0, 200),
+            // still up, cm 10
+            bar(17.0, 14.0, 16.0, 100),
+        ];
+        let force = volume_force(&bars);
+        assert_all_approx_eq(
+            &force,
+            &[16000.0, 12500.0, -30000.0, 120_000.0 / 7.0, 14000.0],
+        );
+        // the oscillator -14750, -41500/21, 18125/63 with a signal line
+        // seeded at their first two's mean
+        assert_pair_approx_eq(
+            signal.calculate(&bars),
+            (18125.0 / 63.0, -490_625.0 / 189.0),
+        );
+
+        assert_eq!(signal.calculate(&bars[..4]), None);
+        let no_volume: Vec<_> = bars
+            .iter()
+            .map(|b| bar(b.high, b.low, b.close, 0))
+            .collect();
+        assert_eq!(signal.calculate(&no_volume), None);
+    }
+
+    #[test]
+    fn test_EaseOfMovement_calculate() {
+        let signal = EaseOfMovement { period: 2 };
+        let bars = [
+            bar(10.0, 8.0, 9.0, 100),
+            bar(12.0, 10.0, 11.0, 200),
+            bar(12.0, 8.0, 10.0, 400),
+            bar(14.0, 12.0, 13.0, 100),
+        ];
+        // midpoints 9, 11, 10, 13 and box ratios 100, 100, 50 give moves of
+        // 0.02, -0.01 and 0.06
+        let emv = signal.calculate(&bars).unwrap();
+        assert_eq!(emv.len(), 2);
+        assert_approx_eq(emv[0], 0.005);
+        assert_approx_eq(emv[1], 0.025);
+        assert_eq!(signal.calculate(&bars[..2]), None);
+        assert_eq!(EaseOfMovement { period: 1 }.calculate(&bars), None);
+        let mut flat = bars;
+        flat[2] = bar(10.0, 10.0, 10.0, 400);
+        assert_eq!(signal.calculate(&flat), None);
+        let mut no_volume = bars;
+        no_volume[3].volume = 0;
+        assert_eq!(signal.calculate(&no_volume), None);
+    }
+
+    #[test]
+    fn test_PercentageVolumeOscillator_calculate() {
+        let signal = PercentageVolumeOscillator {
+            fast: 2,
+            slow: 5,
+            signal: 2,
+        };
+        let mut bars: Vec<Bar> = (0..10).map(|_| bar(10.0, 9.0, 9.5, 1000)).collect();
+        let steady = signal.calculate(&bars).unwrap();
+        // slow EMAs from the fifth bar, signal line from the next
+        assert_eq!(steady.len(), 5);
+        assert!(steady
+            .iter()
+            .all(|(pvo, signal, _)| *pvo == 0.0 && *signal == 0.0));
+
+        bars.extend([bar(10.0, 9.0, 9.5, 5000), bar(10.0, 9.0, 9.5, 5000)]);
+        let surge = signal.calculate(&bars).unwrap();
+        assert_eq!(surge.len(), 7);
+        let (pvo, signal_line, histogram) = surge[surge.len() - 1];
+        assert!(pvo > 0.0);
+        assert!(histogram > 0.0);
+        assert_approx_eq(histogram, pvo - signal_line);
+        // the EMAs of the first surge bar are 11000/3 and 7000/3, the last
+        // steady bar had none
+        assert_approx_eq(surge[5].0, 400.0 / 7.0);
+        assert_approx_eq(surge[4].0, 0.0);
+
+        assert_eq!(signal.calculate(&bars[..5]), None);
+        let no_volume: Vec<Bar> = bars
+            .iter()
+            .map(|b| bar(b.high, b.low, b.close, 0))
+            .collect();
+        assert_eq!(signal.calculate(&no_volume), None);
+    }
+}
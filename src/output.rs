@@ -0,0 +1,836 @@
+//!
+//! Rendering rows of a report in the supported output formats.
+//!
+
+use chrono::{format::Item, format::StrftimeItems, DateTime, Utc};
+use clap::ArgEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::{fmt::Write, str::FromStr, sync::Arc};
+
+///
+/// The formats a tick's rows can be printed in.
+///
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Comma-separated values with a header line
+    Csv,
+    /// Tab-separated values with the same columns and header as CSV
+    Tsv,
+    /// CSV with a column per value of each series, and a header per tick
+    CsvWide,
+    /// One JSON object per line
+    Json,
+    /// One YAML document per tick with a sequence of rows
+    Yaml,
+    /// Influx line protocol, one record per row
+    Influx,
+    /// Aligned columns for reading in a terminal, with a header per tick
+    Table,
+    /// A complete HTML document per tick with a table colored by gains and losses
+    Html,
+    /// A length-prefixed `MessagePack` frame per tick (needs the `msgpack` feature)
+    Msgpack,
+    /// A length-delimited protocol buffers `Tick` of reports (needs the `protobuf` feature)
+    Protobuf,
+}
+
+///
+/// When to color human-readable output.
+///
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// Color if stdout is a terminal and `NO_COLOR` isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    ///
+    /// Decide on coloring given whether the output goes to a terminal and the
+    /// value of the `NO_COLOR` environment variable, which disables coloring
+    /// unless it's empty.
+    ///
+    pub fn enabled(self, tty: bool, no_color: Option<&str>) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => tty && no_color.is_none_or(str::is_empty),
+        }
+    }
+}
+
+///
+/// How timestamp columns are rendered: one of the presets or a chrono
+/// `strftime` string.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimestampFormat {
+    /// `2021-11-01T00:00:00+00:00`
+    Rfc3339,
+    /// `2021-11-01`
+    Date,
+    /// Unix epoch seconds, a number in JSON
+    Epoch,
+    Strftime(Arc<str>),
+}
+
+impl TimestampFormat {
+    pub fn format(&self, timestamp: &DateTime<Utc>) -> String {
+        match self {
+            TimestampFormat::Rfc3339 => timestamp.to_rfc3339(),
+            TimestampFormat::Date => timestamp.format("%Y-%m-%d").to_string(),
+            TimestampFormat::Epoch => timestamp.timestamp().to_string(),
+            TimestampFormat::Strftime(format) => timestamp.format(format.as_ref()).to_string(),
+        }
+    }
+
+    ///
+    /// The timestamp as a JSON value, serialized as before unless another
+    /// format was chosen.
+    ///
+    pub fn to_json(&self, timestamp: &DateTime<Utc>) -> Value {
+        match self {
+            TimestampFormat::Rfc3339 => {
+                serde_json::to_value(timestamp).expect("timestamps always serialize to JSON")
+            }
+            TimestampFormat::Epoch => timestamp.timestamp().into(),
+            TimestampFormat::Date | TimestampFormat::Strftime(_) => self.format(timestamp).into(),
+        }
+    }
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    ///
+    /// Parse a preset name or validate a `strftime` string, which must not
+    /// contain column separators.
+    ///
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "date" => Ok(TimestampFormat::Date),
+            "epoch" => Ok(TimestampFormat::Epoch),
+            "" => Err("the timestamp format is empty".to_string()),
+            _ if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) => {
+                Err(format!("invalid timestamp format '{format}'"))
+            }
+            _ if format.contains([',', '\t']) || format.contains("%t") => Err(format!(
+                "timestamp format '{format}' must not contain commas or tabs"
+            )),
+            _ => Ok(TimestampFormat::Strftime(format.into())),
+        }
+    }
+}
+
+///
+/// When to quote the fields of delimited output.
+///
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    /// Never quote, even fields with delimiters or quotes
+    None,
+    /// Quote fields containing the delimiter, quotes or line breaks
+    Necessary,
+    Always,
+}
+
+///
+/// How human-readable formats present numbers, timestamps and colors, and how
+/// CSV separates its fields.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Decimal places of numbers
+    pub precision: usize,
+    /// Whether the table colors gains and losses
+    pub color: bool,
+    pub timestamps: TimestampFormat,
+    /// The field delimiter of CSV, TSV always uses tabs
+    pub delimiter: char,
+    pub quote: QuoteStyle,
+    /// Whether JSON renders each tick as one array instead of a line per row
+    pub json_array: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            precision: 2,
+            color: false,
+            timestamps: TimestampFormat::Rfc3339,
+            delimiter: ',',
+            quote: QuoteStyle::Necessary,
+            json_array: false,
+        }
+    }
+}
+
+///
+/// Join the fields of a record with the style's delimiter, quoting them as
+/// configured, by way of a `csv` writer. The delimiter must be ASCII.
+///
+pub fn write_record<S: AsRef<str>>(fields: impl IntoIterator<Item = S>, style: &Style) -> String {
+    let fields: Vec<S> = fields.into_iter().collect();
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(u8::try_from(style.delimiter).expect("delimiters are ASCII"))
+        .quote_style(match style.quote {
+            QuoteStyle::None => csv::QuoteStyle::Never,
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+        })
+        .from_writer(vec![]);
+    writer
+        .write_record(fields.iter().map(AsRef::as_ref))
+        .expect("writing to memory can't fail");
+    let mut record = writer.into_inner().expect("writing to memory can't fail");
+    // the line break is up to the caller
+    record.pop();
+    String::from_utf8(record).expect("records of strings are UTF-8")
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+///
+/// A single row of output, e.g. a symbol's report.
+///
+pub trait Row: Serialize {
+    ///
+    /// The comma-separated names of the columns of `csv_fields`.
+    ///
+    const CSV_HEADER: &'static str;
+
+    ///
+    /// Format the fields of the row's CSV record with the style's decimal
+    /// places and timestamps.
+    ///
+    fn csv_fields(&self, style: &Style) -> Vec<String>;
+
+    ///
+    /// The names of the columns of this row's `csv_fields`, which only differ
+    /// from `CSV_HEADER` for rows with optional columns.
+    ///
+    fn csv_columns(&self) -> Vec<String> {
+        Self::CSV_HEADER.split(',').map(str::to_string).collect()
+    }
+
+    ///
+    /// Format the row as a single CSV line (without a line break).
+    ///
+    fn to_csv(&self, style: &Style) -> String {
+        write_record(self.csv_fields(style), style)
+    }
+
+    ///
+    /// The row as a JSON object. Rows with timestamps override this to render
+    /// them in the style's format.
+    ///
+    fn to_json(&self, _timestamps: &TimestampFormat) -> Value {
+        serde_json::to_value(self).expect("rows always serialize to JSON")
+    }
+
+    ///
+    /// Format the row as a single Influx line protocol record (without a
+    /// line break).
+    ///
+    fn to_influx(&self) -> String;
+
+    ///
+    /// The change which colors the row in human-readable formats: green if
+    /// positive, red if negative.
+    ///
+    fn change(&self) -> Option<f64> {
+        None
+    }
+
+    ///
+    /// The series signals of the row by name, spread into a column per value
+    /// in the wide CSV format.
+    ///
+    fn series(&self) -> Vec<(&'static str, Vec<f64>)> {
+        vec![]
+    }
+
+    ///
+    /// The row as a protocol buffers message, if it has one.
+    ///
+    #[cfg(feature = "protobuf")]
+    fn to_protobuf(&self) -> Option<crate::protobuf::ffl::SymbolReport> {
+        None
+    }
+}
+
+///
+/// Escape commas, equal signs and spaces in a line protocol tag key or value.
+///
+pub fn escape_influx_tag(tag: &str) -> String {
+    let mut escaped = String::with_capacity(tag.len());
+    for c in tag.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+///
+/// Quote a line protocol string field value.
+///
+pub fn quote_influx_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+///
+/// The header to print once before all ticks, if the format has one.
+///
+pub fn header<R: Row>(format: OutputFormat, style: &Style) -> Option<String> {
+    delimited(format, style, R::CSV_HEADER)
+}
+
+///
+/// The comma-separated names of columns as a record of the format, if it's
+/// delimited at all.
+///
+pub fn delimited(format: OutputFormat, style: &Style, names: &str) -> Option<String> {
+    match format {
+        OutputFormat::Csv => Some(write_record(names.split(','), style)),
+        OutputFormat::Tsv => Some(write_record(names.split(','), &tsv(style))),
+        OutputFormat::CsvWide
+        | OutputFormat::Json
+        | OutputFormat::Yaml
+        | OutputFormat::Influx
+        | OutputFormat::Table
+        | OutputFormat::Html
+        | OutputFormat::Msgpack
+        | OutputFormat::Protobuf => None,
+    }
+}
+
+///
+/// Render a tick's rows, each line terminated by a line break. Human-readable
+/// formats round numbers to the style's precision, timestamps follow it in
+/// all but Influx, and only the table is ever colored.
+///
+pub fn render<R: Row>(format: OutputFormat, style: &Style, rows: &[R]) -> String {
+    match format {
+        OutputFormat::Csv => rows.iter().map(|r| r.to_csv(style) + "\n").collect(),
+        OutputFormat::Tsv => rows.iter().map(|r| r.to_csv(&tsv(style)) + "\n").collect(),
+        OutputFormat::CsvWide => to_csv_wide(style, rows),
+        OutputFormat::Json if style.json_array => {
+            let rows: Vec<_> = rows.iter().map(|r| r.to_json(&style.timestamps)).collect();
+            serde_json::Value::Array(rows).to_string() + "\n"
+        }
+        OutputFormat::Json => rows
+            .iter()
+            .map(|r| r.to_json(&style.timestamps).to_string() + "\n")
+            .collect(),
+        OutputFormat::Yaml => to_yaml(&style.timestamps, rows),
+        OutputFormat::Influx => rows.iter().map(|r| r.to_influx() + "\n").collect(),
+        OutputFormat::Table => to_table(style, rows),
+        OutputFormat::Html => to_html(style, rows),
+        OutputFormat::Msgpack | OutputFormat::Protobuf => {
+            unreachable!("binary formats are rendered by render_bytes")
+        }
+    }
+}
+
+fn tsv(style: &Style) -> Style {
+    Style {
+        delimiter: '\t',
+        ..style.clone()
+    }
+}
+
+///
+/// Render a tick's rows as bytes, the only way to render binary formats.
+///
+pub fn render_bytes<R: Row>(format: OutputFormat, style: &Style, rows: &[R]) -> Vec<u8> {
+    match format {
+        #[cfg(feature = "msgpack")]
+        OutputFormat::Msgpack => {
+            let rows: Vec<_> = rows.iter().map(|r| r.to_json(&style.timestamps)).collect();
+            crate::msgpack::frame(&rows)
+        }
+        #[cfg(feature = "protobuf")]
+        OutputFormat::Protobuf => crate::protobuf::frame(rows.iter().filter_map(Row::to_protobuf)),
+        _ => render(format, style, rows).into_bytes(),
+    }
+}
+
+///
+/// CSV with the columns of the rows followed by `<name>_1` to `<name>_<n>`
+/// for each of their series, where `n` is the length of the longest one in
+/// the tick. The series are aligned on their last values, those of the
+/// latest close, in `<name>_<n>`, so shorter ones leave their first columns
+/// empty. As the widths change between ticks, each tick starts with its own
+/// header.
+///
+fn to_csv_wide<R: Row>(style: &Style, rows: &[R]) -> String {
+    let columns = R::CSV_HEADER.split(',').count();
+    let mut widths: Vec<(&'static str, usize)> = vec![];
+    for row in rows {
+        for (name, values) in row.series() {
+            match widths.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, width)) => *width = (*width).max(values.len()),
+                None => widths.push((name, values.len())),
+            }
+        }
+    }
+    let mut header: Vec<String> = R::CSV_HEADER.split(',').map(str::to_string).collect();
+    for (name, width) in &widths {
+        header.extend((1..=*width).map(|i| format!("{name}_{i}")));
+    }
+    let mut out = write_record(header, style) + "\n";
+    let p = style.precision;
+    for row in rows {
+        let mut fields = row.csv_fields(style);
+        fields.truncate(columns);
+        let series = row.series();
+        for (name, width) in &widths {
+            let values = series
+                .iter()
+                .find(|(n, _)| n == name)
+                .map_or(&[][..], |(_, values)| &values[..]);
+            let empty = width - values.len();
+            fields.extend((0..empty).map(|_| String::new()));
+            fields.extend(values.iter().map(|v| format!("{v:.p$}")));
+        }
+        out += &write_record(fields, style);
+        out.push('\n');
+    }
+    out
+}
+
+///
+/// The names of the columns of the widest row, for headers matching the rows
+/// rather than `CSV_HEADER`.
+///
+pub fn columns<R: Row>(rows: &[R]) -> Vec<String> {
+    rows.iter()
+        .map(Row::csv_columns)
+        .max_by_key(Vec::len)
+        .unwrap_or_else(|| R::CSV_HEADER.split(',').map(str::to_string).collect())
+}
+
+///
+/// Align the CSV fields of the header and rows, padding each to its widest
+/// cell, and wrap colored rows in ANSI escape codes.
+///
+fn to_table<R: Row>(style: &Style, rows: &[R]) -> String {
+    let header = columns(rows);
+    let lines: Vec<(Vec<String>, Option<f64>)> = std::iter::once((header, None))
+        .chain(rows.iter().map(|r| (r.csv_fields(style), r.change())))
+        .collect();
+    let mut widths = vec![];
+    for (cells, _) in &lines {
+        widths.resize(widths.len().max(cells.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for (cells, change) in lines {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let line = line.trim_end();
+        let code = match change {
+            Some(change) if style.color && change > 0.0 => Some(GREEN),
+            Some(change) if style.color && change < 0.0 => Some(RED),
+            _ => None,
+        };
+        match code {
+            Some(code) => writeln!(out, "{code}{line}{RESET}"),
+            None => writeln!(out, "{line}"),
+        }
+        .expect("formatting into a String");
+    }
+    out
+}
+
+///
+/// Escape the characters with a meaning in HTML text and attributes.
+///
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "table { border-collapse: collapse; font-family: sans-serif; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: right; }
+th { background: #eee; }
+tr.gain { color: #080; }
+tr.loss { color: #c00; }";
+
+///
+/// A complete HTML document with a table of the rows below a header row of
+/// the CSV columns. Rows are classed as `gain` or `loss` to color them, and
+/// rows without an optional column get an empty cell for it.
+///
+fn to_html<R: Row>(style: &Style, rows: &[R]) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Report</title>\n<style>\n{HTML_STYLE}\n</style>\n</head>\n<body>\n<table>\n<thead>\n<tr>"
+    );
+    let columns = columns(rows);
+    for name in &columns {
+        write!(out, "<th>{}</th>", escape_html(name)).expect("formatting into a String");
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+    for row in rows {
+        match row.change() {
+            Some(change) if change > 0.0 => out.push_str("<tr class=\"gain\">"),
+            Some(change) if change < 0.0 => out.push_str("<tr class=\"loss\">"),
+            _ => out.push_str("<tr>"),
+        }
+        let mut cells = row.csv_fields(style);
+        cells.resize(columns.len(), String::new());
+        for cell in cells {
+            write!(out, "<td>{}</td>", escape_html(&cell)).expect("formatting into a String");
+        }
+        out.push_str("</tr>\n");
+    }
+    out + "</tbody>\n</table>\n</body>\n</html>\n"
+}
+
+///
+/// Emit a YAML document with a sequence of mappings, one per row, starting
+/// with a `---` marker so the documents of consecutive ticks stay apart.
+///
+fn to_yaml<R: Row>(timestamps: &TimestampFormat, rows: &[R]) -> String {
+    let rows: Vec<_> = rows.iter().map(|r| r.to_json(timestamps)).collect();
+    let yaml = serde_yaml::to_string(&rows).expect("rows always serialize to YAML");
+    format!("---\n{yaml}")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::report::SymbolReport;
+    use chrono::prelude::*;
+
+    fn style(precision: usize, color: bool) -> Style {
+        Style {
+            precision,
+            color,
+            ..Style::default()
+        }
+    }
+
+    pub fn reports() -> Vec<SymbolReport> {
+        vec![
+            SymbolReport {
+                period_start: Utc.ymd(2021, 11, 1).and_hms(0, 0, 0),
+                symbol: "AAPL".to_string(),
+                last_price: 150.5,
+                pct_change: 2.5,
+                period_min: 140.0,
+                period_max: 155.25,
+                sma: 148.0,
+                last_close_time: Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+                sma_series: None,
+                external: None,
+            },
+            SymbolReport {
+                period_start: Utc.ymd(2021, 11, 1).and_hms(0, 0, 0),
+                symbol: "EURUSD=X".to_string(),
+                last_price: 1.1,
+                pct_change: -0.5,
+                period_min: 1.05,
+                period_max: 1.2,
+                sma: 1.125,
+                last_close_time: Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+                sma_series: None,
+                external: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_csv() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Csv, &Style::default()).as_deref(),
+            Some("period start,symbol,price,change %,min,max,30d avg")
+        );
+        assert_eq!(
+            render(OutputFormat::Csv, &style(2, false), &reports()[..1]),
+            "2021-11-01T00:00:00+00:00,AAPL,$150.50,2.50%,$140.00,$155.25,$148.00\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Csv, &style(1, false), &reports()[1..]),
+            "2021-11-01T00:00:00+00:00,EURUSD=X,$1.1,-0.5%,$1.1,$1.2,$1.1\n"
+        );
+
+        let mut with_external = reports();
+        with_external[0].external = Some(vec![1.0, -0.125]);
+        assert!(
+            render(OutputFormat::Csv, &style(2, false), &with_external[..1])
+                .ends_with(",$148.00,1.00 -0.12\n")
+        );
+    }
+
+    #[test]
+    fn test_render_csv_wide() {
+        use crate::report::SeriesPoint;
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::CsvWide, &Style::default()),
+            None
+        );
+        let mut with_series = reports();
+        with_series[0].sma_series = Some(
+            [147.0, 147.5, 148.0]
+                .iter()
+                .enumerate()
+                .map(|(i, value)| SeriesPoint {
+                    timestamp: Utc.ymd(2021, 11, 3).and_hms(0, 0, 0)
+                        + chrono::Duration::days(i64::try_from(i).unwrap()),
+                    value: *value,
+                })
+                .collect(),
+        );
+        with_series[1].sma_series = Some(vec![SeriesPoint {
+            timestamp: Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+            value: 1.125,
+        }]);
+        let wide = render(OutputFormat::CsvWide, &style(2, false), &with_series);
+        let lines: Vec<_> = wide.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "period start,symbol,price,change %,min,max,30d avg,sma_1,sma_2,sma_3",
+                "2021-11-01T00:00:00+00:00,AAPL,$150.50,2.50%,$140.00,$155.25,$148.00,147.00,147.50,148.00",
+                // both series end on Nov 5
+                "2021-11-01T00:00:00+00:00,EURUSD=X,$1.10,-0.50%,$1.05,$1.20,$1.12,,,1.12",
+            ]
+        );
+        // without series it's CSV with a header per tick
+        assert_eq!(
+            render(OutputFormat::CsvWide, &style(2, false), &reports()[..1]),
+            format!(
+                "{}\n{}",
+                SymbolReport::CSV_HEADER,
+                render(OutputFormat::Csv, &style(2, false), &reports()[..1])
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Html, &Style::default()),
+            None
+        );
+        let mut reports = reports();
+        reports[1].symbol = "<B&B>".to_string();
+        let html = render(OutputFormat::Html, &style(1, false), &reports);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("<tr><th>period start</th><th>symbol</th><th>price</th>"));
+        assert_eq!(html.matches("<tr").count(), 3);
+        assert_eq!(html.matches("<td>").count(), 14);
+        assert!(html.contains(
+            "<tr class=\"gain\"><td>2021-11-01T00:00:00+00:00</td><td>AAPL</td><td>$150.5</td><td>2.5%</td>"
+        ));
+        assert!(html.contains("<tr class=\"loss\"><td>2021-11-01T00:00:00+00:00</td><td>&lt;B&amp;B&gt;</td><td>$1.1</td><td>-0.5%</td>"));
+        assert!(!html.contains('\x1b'));
+
+        // a header cell for every column, even if only some rows have it
+        reports[0].external = Some(vec![1.0, 2.0]);
+        let html = render(OutputFormat::Html, &style(1, false), &reports);
+        assert_eq!(html.matches("<th>").count(), 8);
+        assert!(html.contains("<th>30d avg</th><th>external</th></tr>"));
+        assert_eq!(html.matches("<td>").count(), 16);
+        assert!(html.contains("<td>1.0 2.0</td></tr>"));
+        assert!(html.contains("<td>$1.1</td><td></td></tr>"));
+    }
+
+    #[test]
+    fn test_render_tsv() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Tsv, &Style::default()).as_deref(),
+            Some("period start\tsymbol\tprice\tchange %\tmin\tmax\t30d avg")
+        );
+        assert_eq!(
+            render(OutputFormat::Tsv, &style(2, false), &reports()[..1]),
+            "2021-11-01T00:00:00+00:00\tAAPL\t$150.50\t2.50%\t$140.00\t$155.25\t$148.00\n"
+        );
+        assert_eq!(
+            delimited(OutputFormat::Tsv, &Style::default(), "a,b"),
+            Some("a\tb".to_string())
+        );
+        assert_eq!(
+            delimited(OutputFormat::Json, &Style::default(), "a,b"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_csv_delimiter_quote() {
+        let style = Style {
+            delimiter: ';',
+            quote: QuoteStyle::Always,
+            ..Style::default()
+        };
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Csv, &style).as_deref(),
+            Some(r#""period start";"symbol";"price";"change %";"min";"max";"30d avg""#)
+        );
+        assert_eq!(
+            render(OutputFormat::Csv, &style, &reports()[..1]),
+            "\"2021-11-01T00:00:00+00:00\";\"AAPL\";\"$150.50\";\"2.50%\";\"$140.00\";\"$155.25\";\"$148.00\"\n"
+        );
+
+        let semicolons = Style {
+            delimiter: ';',
+            ..Style::default()
+        };
+        assert_eq!(
+            write_record(["a;b", "c\"d", "e"], &semicolons),
+            "\"a;b\";\"c\"\"d\";e"
+        );
+        let never = Style {
+            quote: QuoteStyle::None,
+            ..semicolons
+        };
+        assert_eq!(write_record(["a;b", "c\"d"], &never), "a;b;c\"d");
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Json, &Style::default()),
+            None
+        );
+        let json = render(OutputFormat::Json, &style(2, false), &reports());
+        let parsed: Vec<SymbolReport> = json
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed, reports());
+    }
+
+    #[test]
+    fn test_render_influx() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Influx, &Style::default()),
+            None
+        );
+        assert_eq!(
+            render(OutputFormat::Influx, &style(2, false), &reports()),
+            "stock,symbol=AAPL price=150.5,change_pct=2.5,min=140,max=155.25,sma=148 1636070400000000000\n\
+             stock,symbol=EURUSD\\=X price=1.1,change_pct=-0.5,min=1.05,max=1.2,sma=1.125 1636070400000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_influx() {
+        assert_eq!(escape_influx_tag("AAPL"), "AAPL");
+        assert_eq!(escape_influx_tag("a b,c=d"), "a\\ b\\,c\\=d");
+        assert_eq!(quote_influx_string(r#"say "hi" \"#), r#""say \"hi\" \\""#);
+    }
+
+    #[test]
+    fn test_render_yaml() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Yaml, &Style::default()),
+            None
+        );
+        let yaml = render(OutputFormat::Yaml, &style(2, false), &reports());
+        assert!(yaml.starts_with("---\n- last_close_time: 2021-11-05T00:00:00Z\n"));
+        assert!(yaml.contains("  symbol: EURUSD=X\n"));
+        assert_eq!(
+            serde_yaml::from_str::<Vec<SymbolReport>>(&yaml).unwrap(),
+            reports()
+        );
+
+        let empty = render::<SymbolReport>(OutputFormat::Yaml, &style(2, false), &[]);
+        assert_eq!(empty, "---\n[]\n");
+        assert!(serde_yaml::from_str::<Vec<SymbolReport>>(&empty)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            header::<SymbolReport>(OutputFormat::Table, &Style::default()),
+            None
+        );
+        let table = render(OutputFormat::Table, &style(2, false), &reports());
+        assert!(!table.contains('\x1b'));
+        assert_eq!(
+            table,
+            "period start               symbol    price    change %  min      max      30d avg\n\
+             2021-11-01T00:00:00+00:00  AAPL      $150.50  2.50%     $140.00  $155.25  $148.00\n\
+             2021-11-01T00:00:00+00:00  EURUSD=X  $1.10    -0.50%    $1.05    $1.20    $1.12\n"
+        );
+
+        let colored = render(OutputFormat::Table, &style(2, true), &reports());
+        let lines: Vec<_> = colored.lines().collect();
+        assert!(lines[0].starts_with("period start"));
+        assert!(lines[1].starts_with("\x1b[32m2021-11-01"));
+        assert!(lines[1].ends_with("$148.00\x1b[0m"));
+        assert!(lines[2].starts_with("\x1b[31m2021-11-01"));
+
+        // never colored outside of the table
+        assert!(!render(OutputFormat::Csv, &style(2, true), &reports()).contains('\x1b'));
+        assert!(!render(OutputFormat::Json, &style(2, true), &reports()).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let instant = Utc.ymd(2021, 11, 1).and_hms(14, 30, 0);
+        let format: TimestampFormat = "%Y-%m-%d".parse().unwrap();
+        assert_eq!(format.format(&instant), "2021-11-01");
+        assert_eq!(format.to_json(&instant), Value::from("2021-11-01"));
+        let epoch: TimestampFormat = "epoch".parse().unwrap();
+        assert_eq!(epoch, TimestampFormat::Epoch);
+        assert_eq!(epoch.format(&instant), "1635777000");
+        assert_eq!(epoch.to_json(&instant), Value::from(1_635_777_000));
+        assert_eq!(
+            TimestampFormat::Rfc3339.format(&instant),
+            "2021-11-01T14:30:00+00:00"
+        );
+        assert!("%Y-%Q".parse::<TimestampFormat>().is_err());
+        assert!("".parse::<TimestampFormat>().is_err());
+        assert!("%d, %b".parse::<TimestampFormat>().is_err());
+        assert!("%d%t%b".parse::<TimestampFormat>().is_err());
+
+        let date = Style {
+            timestamps: TimestampFormat::Date,
+            ..Style::default()
+        };
+        assert!(render(OutputFormat::Csv, &date, &reports()[..1]).starts_with("2021-11-01,AAPL,"));
+        let json: Value =
+            serde_json::from_str(&render(OutputFormat::Json, &date, &reports()[..1])).unwrap();
+        assert_eq!(json["period_start"], "2021-11-01");
+        assert!(render(OutputFormat::Table, &date, &reports()).contains("\n2021-11-01    AAPL"));
+    }
+
+    #[test]
+    fn test_color_choice_enabled() {
+        assert!(ColorChoice::Always.enabled(false, Some("1")));
+        assert!(!ColorChoice::Never.enabled(true, None));
+        assert!(ColorChoice::Auto.enabled(true, None));
+        assert!(ColorChoice::Auto.enabled(true, Some("")));
+        assert!(!ColorChoice::Auto.enabled(true, Some("1")));
+        assert!(!ColorChoice::Auto.enabled(false, None));
+    }
+}
@@ -0,0 +1,95 @@
+//!
+//! A progress counter for the symbols of a tick on stderr.
+//!
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+///
+/// Lines printed to a non-terminal stderr over a whole batch.
+///
+const NON_TTY_UPDATES: usize = 10;
+
+///
+/// Counts completed symbols from any task. On a terminal the counter is
+/// redrawn in place, otherwise a line is printed every tenth of the batch.
+///
+pub struct Progress {
+    completed: AtomicUsize,
+    total: usize,
+    tty: bool,
+}
+
+pub fn format_progress(completed: usize, total: usize) -> String {
+    format!("fetched {completed}/{total}")
+}
+
+impl Progress {
+    pub fn new(total: usize, tty: bool) -> Self {
+        Progress {
+            completed: AtomicUsize::new(0),
+            total,
+            tty,
+        }
+    }
+
+    ///
+    /// Record a completed symbol and print the progress if due.
+    ///
+    pub fn complete_one(&self) {
+        let completed = self.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(update) = self.update(completed) {
+            eprint!("{update}");
+        }
+    }
+
+    ///
+    /// The text to print after `completed` symbols, if any.
+    ///
+    fn update(&self, completed: usize) -> Option<String> {
+        let line = format_progress(completed, self.total);
+        let done = completed >= self.total;
+        if self.tty {
+            Some(format!("\r{line}{}", if done { "\n" } else { "" }))
+        } else {
+            let step = (self.total / NON_TTY_UPDATES).max(1);
+            (done || completed % step == 0).then(|| line + "\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+
+    #[test]
+    fn test_format_progress() {
+        assert_eq!(format_progress(42, 100), "fetched 42/100");
+        assert_eq!(format_progress(0, 0), "fetched 0/0");
+    }
+
+    #[test]
+    fn test_Progress_update() {
+        let tty = Progress::new(3, true);
+        assert_eq!(tty.update(1).unwrap(), "\rfetched 1/3");
+        assert_eq!(tty.update(3).unwrap(), "\rfetched 3/3\n");
+
+        let pipe = Progress::new(100, false);
+        assert_eq!(pipe.update(1), None);
+        assert_eq!(pipe.update(10).unwrap(), "fetched 10/100\n");
+        assert_eq!(pipe.update(42), None);
+        assert_eq!(pipe.update(100).unwrap(), "fetched 100/100\n");
+
+        // small batches print every symbol
+        let pipe = Progress::new(4, false);
+        assert!((1..=4).all(|i| pipe.update(i).is_some()));
+    }
+
+    #[test]
+    fn test_Progress_complete_one() {
+        let progress = Progress::new(2, false);
+        progress.complete_one();
+        progress.complete_one();
+        assert_eq!(progress.completed.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,139 @@
+//!
+//! A protocol buffers encoding of reports for consumers in other languages,
+//! with the messages `prost` generates from `proto/report.proto`.
+//!
+
+use prost::Message;
+
+use crate::report::SymbolReport;
+
+///
+/// The messages of `proto/report.proto`.
+///
+#[allow(clippy::pedantic)]
+pub mod ffl {
+    include!(concat!(env!("OUT_DIR"), "/ffl.rs"));
+}
+
+///
+/// Timestamps are Unix epoch seconds.
+///
+impl From<&SymbolReport> for ffl::SymbolReport {
+    fn from(report: &SymbolReport) -> Self {
+        ffl::SymbolReport {
+            period_start: report.period_start.timestamp(),
+            symbol: report.symbol.clone(),
+            last_price: report.last_price,
+            pct_change: report.pct_change,
+            period_min: report.period_min,
+            period_max: report.period_max,
+            sma: report.sma,
+            sma_series: report
+                .sma_series
+                .iter()
+                .flatten()
+                .map(|point| ffl::SeriesPoint {
+                    timestamp: point.timestamp.timestamp(),
+                    value: point.value,
+                })
+                .collect(),
+            external: report.external.clone().unwrap_or_default(),
+            last_close_time: report.last_close_time.timestamp(),
+        }
+    }
+}
+
+///
+/// A tick's frame: a `Tick` message of the reports prefixed with its length
+/// as a varint, so consumers can decode a stream of ticks one at a time.
+///
+pub fn frame(reports: impl IntoIterator<Item = ffl::SymbolReport>) -> Vec<u8> {
+    ffl::Tick {
+        reports: reports.into_iter().collect(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{tests::reports, OutputFormat, Style};
+    use crate::report::SeriesPoint;
+    use chrono::prelude::*;
+
+    ///
+    /// Empty repeated fields are missing optional series.
+    ///
+    fn non_empty<T>(values: Vec<T>) -> Option<Vec<T>> {
+        Some(values).filter(|v| !v.is_empty())
+    }
+
+    fn decode_report(message: ffl::SymbolReport) -> SymbolReport {
+        SymbolReport {
+            period_start: Utc.timestamp(message.period_start, 0),
+            symbol: message.symbol,
+            last_price: message.last_price,
+            pct_change: message.pct_change,
+            period_min: message.period_min,
+            period_max: message.period_max,
+            sma: message.sma,
+            last_close_time: Utc.timestamp(message.last_close_time, 0),
+            sma_series: non_empty(
+                message
+                    .sma_series
+                    .into_iter()
+                    .map(|point| SeriesPoint {
+                        timestamp: Utc.timestamp(point.timestamp, 0),
+                        value: point.value,
+                    })
+                    .collect(),
+            ),
+            external: non_empty(message.external),
+        }
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut reports = reports();
+        reports[0].sma_series = Some(vec![SeriesPoint {
+            timestamp: Utc.ymd(2021, 11, 2).and_hms(0, 0, 0),
+            value: 147.5,
+        }]);
+        reports[0].external = Some(vec![1.0, -3.0]);
+        reports[1].period_start = Utc.ymd(1960, 1, 1).and_hms(0, 0, 0);
+        let encoded =
+            crate::output::render_bytes(OutputFormat::Protobuf, &Style::default(), &reports);
+        // two ticks in a stream decode one frame at a time
+        let stream = [encoded.clone(), encoded].concat();
+        let mut input = &stream[..];
+        for _ in 0..2 {
+            let tick = ffl::Tick::decode_length_delimited(&mut input).unwrap();
+            let decoded: Vec<_> = tick.reports.into_iter().map(decode_report).collect();
+            assert_eq!(decoded, reports);
+        }
+        assert!(input.is_empty());
+    }
+
+    ///
+    /// A `Tick` of the first report with `external: [1, -3]`, as encoded by
+    /// `protoc --encode=ffl.Tick proto/report.proto` from its text format.
+    ///
+    const GOLDEN_TICK: &[u8] = b"\x0a\x51\x08\x80\xdc\xfc\x8b\x06\x12\x04\x41\x41\x50\x4c\x19\x00\
+          \x00\x00\x00\x00\xd0\x62\x40\x21\x00\x00\x00\x00\x00\x00\x04\x40\
+          \x29\x00\x00\x00\x00\x00\x80\x61\x40\x31\x00\x00\x00\x00\x00\x68\
+          \x63\x40\x39\x00\x00\x00\x00\x00\x80\x62\x40\x4a\x10\x00\x00\x00\
+          \x00\x00\x00\xf0\x3f\x00\x00\x00\x00\x00\x00\x08\xc0\x50\x80\xe8\
+          \x91\x8c\x06";
+
+    #[test]
+    fn test_frame_golden() {
+        let mut report = reports().remove(0);
+        report.external = Some(vec![1.0, -3.0]);
+        let tick = ffl::Tick::decode(GOLDEN_TICK).unwrap();
+        assert_eq!(decode_report(tick.reports[0].clone()), report);
+
+        let encoded = frame([ffl::SymbolReport::from(&report)]);
+        assert_eq!(encoded[0], 0x53);
+        assert_eq!(&encoded[1..], GOLDEN_TICK);
+    }
+}
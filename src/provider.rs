@@ -0,0 +1,768 @@
+//!
+//! Sources of quotes and fetching series from them.
+//!
+
+use chrono::prelude::*;
+use serde::Serialize;
+use std::{
+    fmt,
+    future::Future,
+    io::{self, Error, ErrorKind},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use yahoo_finance_api as yahoo;
+
+use crate::{rng::Rng, series::Series};
+
+///
+/// A source of daily quotes for a symbol.
+///
+pub trait QuoteProvider: Send + Sync + 'static {
+    ///
+    /// Retrieve the quotes between `start` and `end` (inclusive) in any order.
+    ///
+    fn quotes(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Future<Output = io::Result<Vec<yahoo::Quote>>> + Send;
+}
+
+///
+/// The chart API of Yahoo! Finance.
+///
+const YAHOO_CHART_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
+
+///
+/// Quotes from Yahoo! Finance. A `429 Too Many Requests` response is mapped
+/// onto a `FetchError::RateLimited` carrying its `Retry-After`, all other
+/// errors during download onto `io::Errors` as `InvalidData`. A single
+/// instance is meant to be shared by all symbols and ticks.
+///
+pub struct Yahoo {
+    client: reqwest::Client,
+    interval: &'static str,
+}
+
+impl Default for Yahoo {
+    fn default() -> Self {
+        Yahoo {
+            client: reqwest::Client::default(),
+            interval: "1d",
+        }
+    }
+}
+
+impl Yahoo {
+    pub fn new() -> Self {
+        Yahoo::default()
+    }
+
+    ///
+    /// Quotes at another granularity than daily, e.g. `5m` for intraday bars.
+    ///
+    pub fn with_interval(interval: &'static str) -> Self {
+        Yahoo {
+            interval,
+            ..Yahoo::default()
+        }
+    }
+}
+
+impl QuoteProvider for Yahoo {
+    async fn quotes(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> io::Result<Vec<yahoo::Quote>> {
+        let url = format!(
+            "{YAHOO_CHART_URL}/{symbol}?symbol={symbol}&period1={}&period2={}&interval={}&events=div|split",
+            start.timestamp(),
+            end.timestamp(),
+            self.interval
+        );
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|_| Error::from(ErrorKind::InvalidData))?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, Utc::now()));
+            return Err(FetchError::RateLimited { retry_after }.into());
+        }
+        let json = response
+            .error_for_status()
+            .map_err(|_| Error::from(ErrorKind::InvalidData))?
+            .json()
+            .await
+            .map_err(|_| Error::from(ErrorKind::InvalidData))?;
+        yahoo::YResponse::from_json(json)
+            .and_then(|response| response.quotes())
+            .map_err(|_| Error::from(ErrorKind::InvalidData))
+    }
+}
+
+///
+/// Parse a `Retry-After` header, either in seconds or as an HTTP date which
+/// is relative to `now`. Dates in the past mean no delay at all.
+///
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+///
+/// The kinds of failures to fetch a symbol, stable for consumers of the error
+/// objects unlike the messages of the underlying errors. They serialize as a
+/// `kind` field and any details as its siblings.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FetchError {
+    /// The response couldn't be parsed, including unknown symbols on Yahoo
+    InvalidData,
+    TimedOut,
+    NotFound,
+    /// The connection was refused, reset or aborted
+    Connection,
+    /// The provider asked to slow down, possibly saying for how long
+    RateLimited {
+        #[serde(
+            serialize_with = "serialize_secs",
+            skip_serializing_if = "Option::is_none"
+        )]
+        retry_after: Option<Duration>,
+    },
+    Other,
+}
+
+#[allow(clippy::ref_option)]
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(&duration.map(|d| d.as_secs_f64()))
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(f, "rate limited, retry after {}s", retry_after.as_secs()),
+            FetchError::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<FetchError> for io::Error {
+    fn from(err: FetchError) -> Self {
+        Error::other(err)
+    }
+}
+
+impl From<&io::Error> for FetchError {
+    fn from(err: &io::Error) -> Self {
+        if let Some(fetch_error) = err.get_ref().and_then(|e| e.downcast_ref::<FetchError>()) {
+            return *fetch_error;
+        }
+        match err.kind() {
+            ErrorKind::InvalidData => FetchError::InvalidData,
+            ErrorKind::TimedOut => FetchError::TimedOut,
+            ErrorKind::NotFound => FetchError::NotFound,
+            ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted => FetchError::Connection,
+            _ => FetchError::Other,
+        }
+    }
+}
+
+///
+/// Split `[from, to]` into consecutive ranges of at most `chunk` where each
+/// range starts at the end of the previous one. Without a (positive) chunk
+/// size or for an empty range, the range is returned as is.
+///
+pub fn date_chunks(
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    chunk: Option<chrono::Duration>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let chunk = match chunk {
+        Some(chunk) if chunk > chrono::Duration::zero() && from < to => chunk,
+        _ => return vec![(from, to)],
+    };
+    let mut chunks = vec![];
+    let mut start = from;
+    while start < to {
+        let end = (start + chunk).min(to);
+        chunks.push((start, end));
+        start = end;
+    }
+    chunks
+}
+
+///
+/// Retrieve the quotes of a symbol, in chunks if `chunk` is set, and align
+/// their closing prices with their timestamps. Of several quotes with the
+/// same timestamp, e.g. bars at the boundaries of chunks, only the last one
+/// received is kept.
+///
+pub async fn fetch_series<P: QuoteProvider>(
+    provider: &P,
+    symbol: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    chunk: Option<chrono::Duration>,
+) -> io::Result<Series> {
+    let mut quotes = vec![];
+    for (start, end) in date_chunks(from, to, chunk) {
+        quotes.extend(provider.quotes(symbol, start, end).await?);
+    }
+    // the stable sort keeps duplicates in the order received, so reversed
+    // the first of each is the last one received
+    quotes.sort_by_key(|q| q.timestamp);
+    quotes.reverse();
+    quotes.dedup_by_key(|q| q.timestamp);
+    quotes.reverse();
+    Ok(Series::from_quotes(quotes))
+}
+
+///
+/// Random extensions of retry delays by up to `fraction` of them, so the
+/// retries of symbols failing together spread out. Each symbol's retries
+/// draw from a generator of their own, derived from `seed` and the symbol,
+/// so a seed reproduces them no matter in which order symbols fail.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jitter {
+    pub seed: u64,
+    pub fraction: f64,
+}
+
+impl Jitter {
+    ///
+    /// The generator of the jitter of `symbol`'s retries.
+    ///
+    pub fn rng(&self, symbol: &str) -> Rng {
+        Rng::derived(self.seed, symbol)
+    }
+
+    ///
+    /// Extend `delay` by a random part of `fraction` of it.
+    ///
+    pub fn apply(&self, delay: Duration, rng: &mut Rng) -> Duration {
+        delay.mul_f64(1.0 + self.fraction * rng.next_f64())
+    }
+}
+
+///
+/// How often and after which delay to retry a failed fetch of a symbol. When
+/// rate limited, the retry waits for `rate_limit_delay` instead or as long as
+/// the provider asked for, whichever is longer. Jitter only ever extends the
+/// delays.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retry {
+    pub retries: usize,
+    pub delay: Duration,
+    pub rate_limit_delay: Duration,
+    pub jitter: Option<Jitter>,
+}
+
+impl Retry {
+    ///
+    /// How long to wait before retrying after `err`, drawing the jitter, if
+    /// any, from the symbol's `rng`.
+    ///
+    pub fn delay_after(&self, err: &io::Error, rng: Option<&mut Rng>) -> Duration {
+        let delay = match FetchError::from(err) {
+            FetchError::RateLimited { retry_after } => {
+                retry_after.unwrap_or_default().max(self.rate_limit_delay)
+            }
+            _ => self.delay,
+        };
+        match (self.jitter, rng) {
+            (Some(jitter), Some(rng)) => jitter.apply(delay, rng),
+            _ => delay,
+        }
+    }
+}
+
+///
+/// A cap on the total number of retries of all fetches sharing it, e.g. those
+/// of a whole tick, so that a full outage doesn't multiply into a retry storm.
+///
+pub struct RetryBudget {
+    remaining: AtomicUsize,
+}
+
+impl RetryBudget {
+    pub fn new(retries: usize) -> Self {
+        RetryBudget {
+            remaining: AtomicUsize::new(retries),
+        }
+    }
+
+    ///
+    /// Take a retry from the budget if any are left.
+    ///
+    pub fn take(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |r| r.checked_sub(1))
+            .is_ok()
+    }
+}
+
+///
+/// `fetch_series` retrying up to `retry.retries` times as long as the budget,
+/// if any, allows. Once the budget is exhausted, failures are returned right
+/// away. Rate-limited fetches wait as `retry` says before retrying.
+///
+pub async fn fetch_series_retrying<P: QuoteProvider>(
+    provider: &P,
+    symbol: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    chunk: Option<chrono::Duration>,
+    retry: Retry,
+    budget: Option<&RetryBudget>,
+) -> io::Result<Series> {
+    let mut rng = retry.jitter.map(|jitter| jitter.rng(symbol));
+    let mut attempt = 0;
+    loop {
+        match fetch_series(provider, symbol, from, to, chunk).await {
+            Err(err) if attempt < retry.retries && budget.is_none_or(RetryBudget::take) => {
+                attempt += 1;
+                tokio::time::sleep(retry.delay_after(&err, rng.as_mut())).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+///
+/// Retrieve the close of a symbol on `date` or, if there was no trading on
+/// that day, on the last trading day within a week and a half before it.
+/// That's the adjusted close unless `raw_close` asks for the traded one.
+///
+/// # Returns
+///
+/// The trading day and its close or `None` if there was no trading at all in
+/// that window.
+///
+pub async fn close_as_of<P: QuoteProvider>(
+    provider: &P,
+    symbol: &str,
+    date: NaiveDate,
+    raw_close: bool,
+) -> io::Result<Option<(NaiveDate, f64)>> {
+    let end = Utc.from_utc_datetime(&date.and_hms(23, 59, 59));
+    let start = Utc.from_utc_datetime(&(date - chrono::Duration::days(10)).and_hms(0, 0, 0));
+    let mut series = fetch_series(provider, symbol, start, end, None).await?;
+    if raw_close {
+        series.use_raw_closes();
+    }
+    Ok(series
+        .timestamps
+        .iter()
+        .zip(&series.closes)
+        .rev()
+        .map(|(timestamp, close)| (timestamp.date().naive_utc(), *close))
+        .find(|(day, _)| *day <= date))
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Handler =
+        dyn Fn(&str, DateTime<Utc>, DateTime<Utc>) -> io::Result<Vec<yahoo::Quote>> + Send + Sync;
+
+    ///
+    /// The symbol and range of a request.
+    ///
+    pub type Call = (String, DateTime<Utc>, DateTime<Utc>);
+
+    ///
+    /// A provider answering with a closure and recording every request.
+    ///
+    pub struct MockProvider {
+        handler: Box<Handler>,
+        pub calls: Mutex<Vec<Call>>,
+    }
+
+    impl MockProvider {
+        pub fn new(
+            handler: impl Fn(&str, DateTime<Utc>, DateTime<Utc>) -> io::Result<Vec<yahoo::Quote>>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Self {
+            MockProvider {
+                handler: Box::new(handler),
+                calls: Mutex::new(vec![]),
+            }
+        }
+
+        ///
+        /// A provider with a daily quote at midnight for every day in the
+        /// requested range, closing at the day of the month.
+        ///
+        pub fn daily() -> Self {
+            MockProvider::new(|_, start, end| Ok(daily_quotes(start, end, |_| true)))
+        }
+
+        ///
+        /// Like `daily` but only trading from Monday to Friday.
+        ///
+        pub fn weekdays() -> Self {
+            MockProvider::new(|_, start, end| {
+                Ok(daily_quotes(start, end, |day| {
+                    day.weekday().number_from_monday() <= 5
+                }))
+            })
+        }
+
+        pub fn call_count(&self) -> usize {
+            self.calls.lock().unwrap().len()
+        }
+    }
+
+    fn daily_quotes(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        trading: impl Fn(DateTime<Utc>) -> bool,
+    ) -> Vec<yahoo::Quote> {
+        let mut day = start.date().and_hms(0, 0, 0);
+        let mut quotes = vec![];
+        while day <= end {
+            if trading(day) {
+                #[allow(clippy::cast_sign_loss)]
+                let timestamp = day.timestamp() as u64;
+                quotes.push(crate::series::tests::quote(timestamp, f64::from(day.day())));
+            }
+            day = day + chrono::Duration::days(1);
+        }
+        quotes
+    }
+
+    impl QuoteProvider for MockProvider {
+        async fn quotes(
+            &self,
+            symbol: &str,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+        ) -> io::Result<Vec<yahoo::Quote>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((symbol.to_string(), start, end));
+            (self.handler)(symbol, start, end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockProvider;
+    use super::*;
+
+    #[test]
+    fn test_fetch_error_from() {
+        let kind = |kind| FetchError::from(&Error::from(kind));
+        assert_eq!(kind(ErrorKind::InvalidData), FetchError::InvalidData);
+        assert_eq!(kind(ErrorKind::ConnectionReset), FetchError::Connection);
+        assert_eq!(kind(ErrorKind::Interrupted), FetchError::Other);
+        assert_eq!(
+            serde_json::to_string(&FetchError::TimedOut).unwrap(),
+            r#"{"kind":"timed_out"}"#
+        );
+
+        let rate_limited = FetchError::RateLimited {
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(FetchError::from(&Error::from(rate_limited)), rate_limited);
+        assert_eq!(
+            serde_json::to_string(&rate_limited).unwrap(),
+            r#"{"kind":"rate_limited","retry_after":30.0}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&FetchError::RateLimited { retry_after: None }).unwrap(),
+            r#"{"kind":"rate_limited"}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.ymd(2021, 11, 1).and_hms(12, 0, 0);
+        assert_eq!(
+            parse_retry_after(" 90 ", now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Nov 2021 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Nov 2021 11:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_series_retry_rate_limited() {
+        let limited = AtomicUsize::new(1);
+        let provider = MockProvider::new(move |_, _, _| {
+            if limited
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |l| l.checked_sub(1))
+                .is_ok()
+            {
+                Err(FetchError::RateLimited {
+                    retry_after: Some(Duration::from_millis(200)),
+                }
+                .into())
+            } else {
+                Ok(vec![crate::series::tests::quote(1_609_459_200, 1.0)])
+            }
+        });
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let retry = Retry {
+            retries: 1,
+            delay: Duration::ZERO,
+            rate_limit_delay: Duration::from_millis(50),
+            jitter: None,
+        };
+        let started = std::time::Instant::now();
+        let series = fetch_series_retrying(&provider, "AAPL", from, from, None, retry, None)
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(series.closes, vec![1.0]);
+        assert_eq!(provider.call_count(), 2);
+
+        let generic = Error::from(ErrorKind::InvalidData);
+        assert_eq!(retry.delay_after(&generic, None), Duration::ZERO);
+        let unspecified = FetchError::RateLimited { retry_after: None }.into();
+        assert_eq!(
+            retry.delay_after(&unspecified, None),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn test_date_chunks() {
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let to = Utc.ymd(2021, 1, 11).and_hms(0, 0, 0);
+        assert_eq!(
+            date_chunks(from, to, Some(chrono::Duration::days(4))),
+            vec![
+                (from, Utc.ymd(2021, 1, 5).and_hms(0, 0, 0)),
+                (
+                    Utc.ymd(2021, 1, 5).and_hms(0, 0, 0),
+                    Utc.ymd(2021, 1, 9).and_hms(0, 0, 0)
+                ),
+                (Utc.ymd(2021, 1, 9).and_hms(0, 0, 0), to),
+            ]
+        );
+        assert_eq!(date_chunks(from, to, None), vec![(from, to)]);
+        assert_eq!(
+            date_chunks(from, to, Some(chrono::Duration::zero())),
+            vec![(from, to)]
+        );
+        assert_eq!(
+            date_chunks(to, from, Some(chrono::Duration::days(4))),
+            vec![(to, from)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_series_chunked() {
+        let provider = MockProvider::daily();
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let to = Utc.ymd(2021, 1, 31).and_hms(0, 0, 0);
+
+        let series = fetch_series(&provider, "AAPL", from, to, Some(chrono::Duration::days(7)))
+            .await
+            .unwrap();
+        assert_eq!(provider.call_count(), 5);
+        assert_eq!(series.timestamps.len(), 31);
+        assert_eq!(series.timestamps.first(), Some(&from));
+        assert_eq!(series.timestamps.last(), Some(&to));
+        assert!(series.timestamps.windows(2).all(|w| w[0] < w[1]));
+        let expected: Vec<f64> = (1..=31).map(f64::from).collect();
+        assert_eq!(series.closes, expected);
+
+        let unchunked = MockProvider::daily();
+        let series = fetch_series(&unchunked, "AAPL", from, to, None)
+            .await
+            .unwrap();
+        assert_eq!(unchunked.call_count(), 1);
+        assert_eq!(series.closes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_close_as_of() {
+        let provider = MockProvider::weekdays();
+
+        // Sunday, 2021-11-07
+        let sunday = NaiveDate::from_ymd(2021, 11, 7);
+        let close = close_as_of(&provider, "AAPL", sunday, false).await.unwrap();
+        assert_eq!(close, Some((NaiveDate::from_ymd(2021, 11, 5), 5.0)));
+
+        let monday = NaiveDate::from_ymd(2021, 11, 8);
+        let close = close_as_of(&provider, "AAPL", monday, false).await.unwrap();
+        assert_eq!(close, Some((monday, 8.0)));
+
+        let empty = MockProvider::new(|_, _, _| Ok(vec![]));
+        assert_eq!(
+            close_as_of(&empty, "AAPL", monday, false).await.unwrap(),
+            None
+        );
+
+        // traded at twice the adjusted close before a split
+        let split = MockProvider::new(|_, _, end| {
+            Ok(vec![yahoo::Quote {
+                close: 200.0,
+                ..crate::series::tests::quote(end.timestamp().unsigned_abs(), 100.0)
+            }])
+        });
+        let close = close_as_of(&split, "AAPL", monday, true).await.unwrap();
+        assert_eq!(close, Some((monday, 200.0)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_series_duplicates() {
+        use crate::series::tests::quote;
+        let provider = MockProvider::new(|_, _, _| {
+            Ok(vec![
+                quote(1_635_984_000, 2.0),
+                quote(1_635_897_600, 1.0),
+                quote(1_635_984_000, 2.5),
+                quote(1_636_070_400, 3.0),
+                quote(1_635_984_000, 2.75),
+            ])
+        });
+        let from = Utc.ymd(2021, 11, 1).and_hms(0, 0, 0);
+        let series = fetch_series(&provider, "AAPL", from, from, None)
+            .await
+            .unwrap();
+        assert_eq!(series.timestamps.len(), 3);
+        assert_eq!(series.closes, vec![1.0, 2.75, 3.0]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_series_retry_budget() {
+        let provider = MockProvider::new(|_, _, _| Err(Error::from(ErrorKind::InvalidData)));
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let retry = Retry {
+            retries: 2,
+            delay: Duration::ZERO,
+            rate_limit_delay: Duration::ZERO,
+            jitter: None,
+        };
+        let budget = RetryBudget::new(3);
+        for symbol in ["AAPL", "MSFT", "UBER"] {
+            let series =
+                fetch_series_retrying(&provider, symbol, from, from, None, retry, Some(&budget))
+                    .await;
+            assert_eq!(series.unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+        // AAPL used two retries, MSFT the last one and UBER got none
+        let calls: Vec<_> = provider
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(symbol, _, _)| symbol.clone())
+            .collect();
+        assert_eq!(calls, ["AAPL", "AAPL", "AAPL", "MSFT", "MSFT", "UBER"]);
+        assert!(!budget.take());
+
+        // without a budget every symbol gets its retries
+        let unbudgeted = MockProvider::new(|_, _, _| Err(Error::from(ErrorKind::InvalidData)));
+        for symbol in ["AAPL", "MSFT"] {
+            let _ = fetch_series_retrying(&unbudgeted, symbol, from, from, None, retry, None).await;
+        }
+        assert_eq!(unbudgeted.call_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_series_retry_recovers() {
+        let failures = AtomicUsize::new(1);
+        let provider = MockProvider::new(move |_, _, _| {
+            if failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| f.checked_sub(1))
+                .is_ok()
+            {
+                Err(Error::from(ErrorKind::InvalidData))
+            } else {
+                Ok(vec![crate::series::tests::quote(1_609_459_200, 1.0)])
+            }
+        });
+        let from = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let retry = Retry {
+            retries: 1,
+            delay: Duration::ZERO,
+            rate_limit_delay: Duration::ZERO,
+            jitter: None,
+        };
+        let series = fetch_series_retrying(&provider, "AAPL", from, from, None, retry, None)
+            .await
+            .unwrap();
+        assert_eq!(series.closes, vec![1.0]);
+        assert_eq!(provider.call_count(), 2);
+    }
+
+    #[test]
+    fn test_retry_jitter_seeded() {
+        let retry = |seed| Retry {
+            retries: 1,
+            delay: Duration::from_secs(1),
+            rate_limit_delay: Duration::ZERO,
+            jitter: Some(Jitter {
+                seed,
+                fraction: 0.5,
+            }),
+        };
+        let err = Error::from(ErrorKind::InvalidData);
+        let delays = |retry: Retry, symbol| -> Vec<Duration> {
+            let mut rng = retry.jitter.map(|jitter| jitter.rng(symbol));
+            (0..10)
+                .map(|_| retry.delay_after(&err, rng.as_mut()))
+                .collect()
+        };
+        let first = delays(retry(42), "AAPL");
+        assert_eq!(first, delays(retry(42), "AAPL"));
+        assert_ne!(first, delays(retry(43), "AAPL"));
+        // every symbol has its own sequence
+        assert_ne!(first, delays(retry(42), "MSFT"));
+        assert!(first
+            .iter()
+            .all(|d| (Duration::from_secs(1)..Duration::from_millis(1500)).contains(d)));
+        assert!(first.windows(2).any(|w| w[0] != w[1]));
+        // no jitter without a generator
+        assert_eq!(retry(42).delay_after(&err, None), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,500 @@
+//!
+//! Per-symbol reports and comparisons between them.
+//!
+
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use serde_json::Value;
+use std::io::{self, Error, ErrorKind};
+
+use crate::output::{escape_influx_tag, quote_influx_string, Row, Style, TimestampFormat};
+use crate::provider::FetchError;
+
+///
+/// A single value of a series signal at the time of the close it ends at.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SeriesPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+///
+/// The signals calculated for a single symbol over the reporting period. The
+/// full moving average series is only included on request and only in the
+/// structured formats. The values of an external signal are appended as a
+/// last, space-separated CSV column.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolReport {
+    pub period_start: DateTime<Utc>,
+    pub symbol: String,
+    pub last_price: f64,
+    pub pct_change: f64,
+    pub period_min: f64,
+    pub period_max: f64,
+    pub sma: f64,
+    /// The time of the last close, which `last_price` is
+    pub last_close_time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sma_series: Option<Vec<SeriesPoint>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external: Option<Vec<f64>>,
+}
+
+impl Row for SymbolReport {
+    const CSV_HEADER: &'static str = "period start,symbol,price,change %,min,max,30d avg";
+
+    fn csv_fields(&self, style: &Style) -> Vec<String> {
+        let p = style.precision;
+        let mut fields = vec![
+            style.timestamps.format(&self.period_start),
+            self.symbol.clone(),
+            format!("${:.p$}", self.last_price),
+            format!("{:.p$}%", self.pct_change),
+            format!("${:.p$}", self.period_min),
+            format!("${:.p$}", self.period_max),
+            format!("${:.p$}", self.sma),
+        ];
+        if let Some(external) = &self.external {
+            let values: Vec<_> = external.iter().map(|v| format!("{v:.p$}")).collect();
+            fields.push(values.join(" "));
+        }
+        fields
+    }
+
+    fn csv_columns(&self) -> Vec<String> {
+        let mut columns: Vec<_> = Self::CSV_HEADER.split(',').map(str::to_string).collect();
+        if self.external.is_some() {
+            columns.push("external".to_string());
+        }
+        columns
+    }
+
+    fn to_json(&self, timestamps: &TimestampFormat) -> Value {
+        let mut json = serde_json::to_value(self).expect("reports always serialize to JSON");
+        json["period_start"] = timestamps.to_json(&self.period_start);
+        json["last_close_time"] = timestamps.to_json(&self.last_close_time);
+        json
+    }
+
+    ///
+    /// A `stock` record tagged with the symbol and timestamped with the
+    /// time of the last close in nanoseconds, so every tick's record is a new
+    /// point rather than overwriting the previous one.
+    ///
+    fn to_influx(&self) -> String {
+        format!(
+            "stock,symbol={} price={},change_pct={},min={},max={},sma={} {}",
+            escape_influx_tag(&self.symbol),
+            self.last_price,
+            self.pct_change,
+            self.period_min,
+            self.period_max,
+            self.sma,
+            self.last_close_time.timestamp_nanos()
+        )
+    }
+
+    fn change(&self) -> Option<f64> {
+        Some(self.pct_change)
+    }
+
+    fn series(&self) -> Vec<(&'static str, Vec<f64>)> {
+        let mut series = vec![];
+        if let Some(sma_series) = &self.sma_series {
+            series.push(("sma", sma_series.iter().map(|p| p.value).collect()));
+        }
+        if let Some(external) = &self.external {
+            series.push(("external", external.clone()));
+        }
+        series
+    }
+
+    #[cfg(feature = "protobuf")]
+    fn to_protobuf(&self) -> Option<crate::protobuf::ffl::SymbolReport> {
+        Some(self.into())
+    }
+}
+
+///
+/// The change of a symbol's signals between two reporting periods.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub symbol: String,
+    pub pct_change_delta: f64,
+    pub sma_delta: f64,
+}
+
+impl Row for ReportDiff {
+    const CSV_HEADER: &'static str = "symbol,change % delta,30d avg delta";
+
+    fn csv_fields(&self, style: &Style) -> Vec<String> {
+        let p = style.precision;
+        vec![
+            self.symbol.clone(),
+            format!("{:.p$}%", self.pct_change_delta),
+            format!("${:.p$}", self.sma_delta),
+        ]
+    }
+
+    ///
+    /// A `stock_diff` record tagged with the symbol, timestamped by the server.
+    ///
+    fn to_influx(&self) -> String {
+        format!(
+            "stock_diff,symbol={} change_pct_delta={},sma_delta={}",
+            escape_influx_tag(&self.symbol),
+            self.pct_change_delta,
+            self.sma_delta
+        )
+    }
+
+    fn change(&self) -> Option<f64> {
+        Some(self.pct_change_delta)
+    }
+}
+
+///
+/// A symbol's close on a requested date. On weekends and holidays this is the
+/// close of the most recent prior trading day instead.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsOfClose {
+    pub symbol: String,
+    pub as_of: NaiveDate,
+    pub date: NaiveDate,
+    pub close: f64,
+    pub prior_trading_day: bool,
+}
+
+impl Row for AsOfClose {
+    const CSV_HEADER: &'static str = "symbol,as of,date,close,note";
+
+    fn csv_fields(&self, style: &Style) -> Vec<String> {
+        let p = style.precision;
+        vec![
+            self.symbol.clone(),
+            self.as_of.to_string(),
+            self.date.to_string(),
+            format!("${:.p$}", self.close),
+            if self.prior_trading_day {
+                "prior trading day"
+            } else {
+                ""
+            }
+            .to_string(),
+        ]
+    }
+
+    ///
+    /// A `stock_close` record tagged with the symbol and timestamped with the
+    /// midnight of the trading day in nanoseconds.
+    ///
+    fn to_influx(&self) -> String {
+        format!(
+            "stock_close,symbol={} close={},prior_trading_day={} {}",
+            escape_influx_tag(&self.symbol),
+            self.close,
+            self.prior_trading_day,
+            self.date.and_hms(0, 0, 0).timestamp_nanos()
+        )
+    }
+}
+
+///
+/// An aggregate over the reports of the whole watchlist for a single tick.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistSummary {
+    pub up: usize,
+    pub down: usize,
+    pub avg_pct_change: f64,
+    pub best_symbol: String,
+    pub best_pct_change: f64,
+    pub worst_symbol: String,
+    pub worst_pct_change: f64,
+}
+
+impl Row for WatchlistSummary {
+    const CSV_HEADER: &'static str = "summary,up,down,avg change %,best,worst";
+
+    fn csv_fields(&self, style: &Style) -> Vec<String> {
+        let p = style.precision;
+        vec![
+            "summary".to_string(),
+            self.up.to_string(),
+            self.down.to_string(),
+            format!("{:.p$}%", self.avg_pct_change),
+            format!("{} ({:.p$}%)", self.best_symbol, self.best_pct_change),
+            format!("{} ({:.p$}%)", self.worst_symbol, self.worst_pct_change),
+        ]
+    }
+
+    ///
+    /// A `watchlist` record, timestamped by the server.
+    ///
+    fn to_influx(&self) -> String {
+        format!(
+            "watchlist up={}i,down={}i,avg_change_pct={},best={},best_change_pct={},worst={},worst_change_pct={}",
+            self.up,
+            self.down,
+            self.avg_pct_change,
+            quote_influx_string(&self.best_symbol),
+            self.best_pct_change,
+            quote_influx_string(&self.worst_symbol),
+            self.worst_pct_change
+        )
+    }
+
+    fn change(&self) -> Option<f64> {
+        Some(self.avg_pct_change)
+    }
+}
+
+///
+/// A symbol whose data couldn't be fetched. In JSON output these follow the
+/// tick's rows, one object per line.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SymbolError {
+    pub symbol: String,
+    pub error: String,
+    #[serde(flatten)]
+    pub kind: FetchError,
+}
+
+impl SymbolError {
+    pub fn new(symbol: &str, err: &io::Error) -> Self {
+        SymbolError {
+            symbol: symbol.to_string(),
+            error: err.to_string(),
+            kind: FetchError::from(err),
+        }
+    }
+}
+
+impl From<SymbolError> for io::Error {
+    fn from(err: SymbolError) -> Self {
+        let kind = match err.kind {
+            FetchError::InvalidData => ErrorKind::InvalidData,
+            FetchError::TimedOut => ErrorKind::TimedOut,
+            FetchError::NotFound => ErrorKind::NotFound,
+            FetchError::Connection => ErrorKind::ConnectionAborted,
+            FetchError::RateLimited { .. } | FetchError::Other => ErrorKind::Other,
+        };
+        Error::new(kind, format!("{}: {}", err.symbol, err.error))
+    }
+}
+
+///
+/// Count the symbols that went up or down (unchanged symbols are neither),
+/// average their percentage change and find the best and worst performer. Ties
+/// for best or worst resolve to the first report.
+///
+/// # Returns
+///
+/// The summary or `None` if there are no reports.
+///
+pub fn summarize(reports: &[SymbolReport]) -> Option<WatchlistSummary> {
+    let first = reports.first()?;
+    let (mut best, mut worst) = (first, first);
+    for report in reports {
+        if report.pct_change > best.pct_change {
+            best = report;
+        }
+        if report.pct_change < worst.pct_change {
+            worst = report;
+        }
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let avg_pct_change = reports.iter().map(|r| r.pct_change).sum::<f64>() / reports.len() as f64;
+    Some(WatchlistSummary {
+        up: reports.iter().filter(|r| r.pct_change > 0.0).count(),
+        down: reports.iter().filter(|r| r.pct_change < 0.0).count(),
+        avg_pct_change,
+        best_symbol: best.symbol.clone(),
+        best_pct_change: best.pct_change,
+        worst_symbol: worst.symbol.clone(),
+        worst_pct_change: worst.pct_change,
+    })
+}
+
+///
+/// Pair up the reports of the same symbols from two periods and calculate the
+/// change of the percentage change and moving average between them. Symbols
+/// which are missing from either period are skipped.
+///
+pub fn diff_reports(current: &[SymbolReport], previous: &[SymbolReport]) -> Vec<ReportDiff> {
+    current
+        .iter()
+        .filter_map(|c| {
+            previous
+                .iter()
+                .find(|p| p.symbol == c.symbol)
+                .map(|p| ReportDiff {
+                    symbol: c.symbol.clone(),
+                    pct_change_delta: c.pct_change - p.pct_change,
+                    sma_delta: c.sma - p.sma,
+                })
+        })
+        .collect()
+}
+
+///
+/// Remembers the reports of the previous tick to only pass on those whose
+/// percentage change moved by more than `threshold` percentage points since.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alerts {
+    threshold: f64,
+    previous: Option<Vec<SymbolReport>>,
+}
+
+impl Alerts {
+    pub fn new(threshold: f64) -> Self {
+        Alerts {
+            threshold,
+            previous: None,
+        }
+    }
+
+    ///
+    /// Keep `reports` for the next tick and return the ones that changed
+    /// meaningfully: all on the first tick, and afterwards those of new
+    /// symbols or with a change that moved by more than the threshold.
+    ///
+    pub fn filter(&mut self, reports: &[SymbolReport]) -> Vec<SymbolReport> {
+        let changed = match &self.previous {
+            None => reports.to_vec(),
+            Some(previous) => reports
+                .iter()
+                .filter(|c| {
+                    previous
+                        .iter()
+                        .find(|p| p.symbol == c.symbol)
+                        .is_none_or(|p| (c.pct_change - p.pct_change).abs() > self.threshold)
+                })
+                .cloned()
+                .collect(),
+        };
+        self.previous = Some(reports.to_vec());
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::approx::assert_approx_eq;
+
+    fn report(symbol: &str, pct_change: f64, sma: f64) -> SymbolReport {
+        SymbolReport {
+            period_start: Utc.ymd(2021, 11, 1).and_hms(0, 0, 0),
+            symbol: symbol.to_string(),
+            last_price: 10.0,
+            pct_change,
+            period_min: 5.0,
+            period_max: 15.0,
+            sma,
+            last_close_time: Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+            sma_series: None,
+            external: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports() {
+        let current = vec![report("AAPL", 5.0, 150.0), report("MSFT", -2.0, 300.0)];
+        let previous = vec![report("MSFT", 1.0, 310.0), report("AAPL", 2.5, 140.0)];
+        assert_eq!(
+            diff_reports(&current, &previous),
+            vec![
+                ReportDiff {
+                    symbol: "AAPL".to_string(),
+                    pct_change_delta: 2.5,
+                    sma_delta: 10.0
+                },
+                ReportDiff {
+                    symbol: "MSFT".to_string(),
+                    pct_change_delta: -3.0,
+                    sma_delta: -10.0
+                },
+            ]
+        );
+
+        // symbols missing from either period are skipped
+        let previous = vec![report("AAPL", 2.5, 140.0), report("GOOG", 1.0, 1.0)];
+        assert_eq!(diff_reports(&current, &previous).len(), 1);
+        assert!(diff_reports(&current, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_SymbolError_serialize() {
+        let err = Error::from(FetchError::RateLimited {
+            retry_after: Some(std::time::Duration::from_secs(30)),
+        });
+        assert_eq!(
+            serde_json::to_string(&SymbolError::new("AAPL", &err)).unwrap(),
+            r#"{"symbol":"AAPL","error":"rate limited, retry after 30s","kind":"rate_limited","retry_after":30.0}"#
+        );
+        let err = Error::from(ErrorKind::NotFound);
+        assert_eq!(
+            serde_json::to_string(&SymbolError::new("AAPL", &err)).unwrap(),
+            r#"{"symbol":"AAPL","error":"entity not found","kind":"not_found"}"#
+        );
+    }
+
+    #[test]
+    fn test_summarize() {
+        let reports = vec![
+            report("AAPL", 5.0, 150.0),
+            report("MSFT", -2.0, 300.0),
+            report("UBER", 0.0, 40.0),
+            report("GOOG", 1.0, 2900.0),
+        ];
+        let summary = summarize(&reports).unwrap();
+        assert_eq!(summary.up, 2);
+        assert_eq!(summary.down, 1);
+        assert_approx_eq(summary.avg_pct_change, 1.0);
+        assert_eq!(summary.best_symbol, "AAPL");
+        assert_eq!(summary.worst_symbol, "MSFT");
+        assert_eq!(
+            summary.to_csv(&Style {
+                precision: 1,
+                ..Style::default()
+            }),
+            "summary,2,1,1.0%,AAPL (5.0%),MSFT (-2.0%)"
+        );
+        assert_eq!(
+            summary.to_influx(),
+            r#"watchlist up=2i,down=1i,avg_change_pct=1,best="AAPL",best_change_pct=5,worst="MSFT",worst_change_pct=-2"#
+        );
+
+        assert_eq!(summarize(&[]), None);
+    }
+
+    #[test]
+    fn test_Alerts_filter() {
+        let mut alerts = Alerts::new(1.0);
+        let first = vec![report("AAPL", 2.0, 150.0), report("MSFT", -1.0, 300.0)];
+        assert_eq!(alerts.filter(&first), first);
+
+        let second = vec![
+            report("AAPL", 2.5, 151.0),
+            report("MSFT", -2.5, 301.0),
+            report("UBER", 0.0, 40.0),
+        ];
+        assert_eq!(
+            alerts.filter(&second),
+            vec![report("MSFT", -2.5, 301.0), report("UBER", 0.0, 40.0)]
+        );
+
+        // compared to the previous tick, not the last emitted one
+        let third = vec![report("AAPL", 3.2, 152.0)];
+        assert!(alerts.filter(&third).is_empty());
+    }
+}
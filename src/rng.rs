@@ -0,0 +1,87 @@
+//!
+//! A small seeded random number generator, so that runs with the same seed
+//! behave the same.
+//!
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+///
+/// The `SplitMix64` generator: fast, with a 64 bit state and good enough for
+/// jitter, but not for anything cryptographic.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    ///
+    /// A generator for `key` derived from `seed`, so each key gets its own
+    /// sequence that a seed reproduces.
+    ///
+    pub fn derived(seed: u64, key: &str) -> Self {
+        key.bytes().fold(Rng::seeded(seed), |mut rng, byte| {
+            Rng::seeded(rng.next_u64() ^ u64::from(byte))
+        })
+    }
+
+    ///
+    /// A generator seeded from the randomly keyed hasher of the standard
+    /// library, different for every run.
+    ///
+    pub fn from_entropy() -> Self {
+        Rng::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    ///
+    /// A uniformly distributed number in `[0, 1)`.
+    ///
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill the mantissa exactly
+        #[allow(clippy::cast_precision_loss)]
+        let value = (self.next_u64() >> 11) as f64;
+        // 2^53
+        value / 9_007_199_254_740_992.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+
+    #[test]
+    fn test_Rng_seeded() {
+        let (mut a, mut b) = (Rng::seeded(7), Rng::seeded(7));
+        let sequence: Vec<_> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(sequence, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        // the reference output of SplitMix64 seeded with zero
+        assert_eq!(Rng::seeded(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+        let mut rng = Rng::seeded(42);
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+    }
+
+    #[test]
+    fn test_Rng_derived() {
+        let first = |seed, key| Rng::derived(seed, key).next_u64();
+        assert_eq!(first(7, "AAPL"), first(7, "AAPL"));
+        assert_ne!(first(7, "AAPL"), first(7, "MSFT"));
+        assert_ne!(first(7, "AAPL"), first(8, "AAPL"));
+        assert_ne!(first(7, "AB"), first(7, "BA"));
+    }
+}
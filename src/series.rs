@@ -0,0 +1,306 @@
+//!
+//! Closing prices aligned with the timestamps of their quotes.
+//!
+
+use chrono::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+};
+use yahoo_finance_api as yahoo;
+
+use crate::ohlc::Bar;
+
+///
+/// A series of closing prices, adjusted unless replaced by the raw ones with
+/// `use_raw_closes`, in chronological order where
+/// `timestamps[i]` is the time of `closes[i]` and the full quote of that
+/// period is `bars[i]`.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    pub timestamps: Vec<DateTime<Utc>>,
+    pub closes: Vec<f64>,
+    pub bars: Vec<Bar>,
+}
+
+impl Series {
+    ///
+    /// Sort the quotes by time and extract their adjusted closes and bars.
+    /// Quotes with a timestamp that can't be represented are dropped.
+    ///
+    pub fn from_quotes(mut quotes: Vec<yahoo::Quote>) -> Self {
+        quotes.sort_by_cached_key(|k| k.timestamp);
+        let mut series = Series::default();
+        for q in &quotes {
+            let timestamp = i64::try_from(q.timestamp)
+                .ok()
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single());
+            if let Some(timestamp) = timestamp {
+                series.timestamps.push(timestamp);
+                series.closes.push(q.adjclose);
+                series.bars.push(Bar {
+                    open: q.open,
+                    high: q.high,
+                    low: q.low,
+                    close: q.close,
+                    volume: q.volume,
+                });
+            }
+        }
+        series
+    }
+
+    ///
+    /// A series of closes without quotes, one day apart starting at `start`.
+    /// Their bars have no range and no volume.
+    ///
+    pub fn from_closes(closes: Vec<f64>, start: DateTime<Utc>) -> Self {
+        let timestamps =
+            std::iter::successors(Some(start), |t| Some(*t + chrono::Duration::days(1)))
+                .take(closes.len())
+                .collect();
+        let bars = closes
+            .iter()
+            .map(|close| Bar {
+                open: *close,
+                high: *close,
+                low: *close,
+                close: *close,
+                volume: 0,
+            })
+            .collect();
+        Series {
+            timestamps,
+            closes,
+            bars,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.closes.is_empty()
+    }
+
+    ///
+    /// Replace the adjusted closes by the raw closes of the bars. Adjusted
+    /// closes scale earlier prices for later splits and dividends so returns
+    /// are continuous, raw closes are the prices actually traded at the time.
+    ///
+    pub fn use_raw_closes(&mut self) {
+        self.closes = self.bars.iter().map(|bar| bar.close).collect();
+    }
+
+    ///
+    /// Keep only the last `n` bars.
+    ///
+    pub fn tail(&mut self, n: usize) {
+        let start = self.closes.len().saturating_sub(n);
+        self.timestamps.drain(..start);
+        self.closes.drain(..start);
+        self.bars.drain(..start);
+    }
+
+    ///
+    /// The quotes as CSV with a header, one line per quote with its time,
+    /// prices and volume, for looking at exactly what the provider returned.
+    /// Only meaningful before the closes are replaced or deduplicated.
+    ///
+    pub fn quotes_csv(&self) -> String {
+        let mut csv = String::from("timestamp,open,high,low,close,adjclose,volume\n");
+        for ((timestamp, close), bar) in self.timestamps.iter().zip(&self.closes).zip(&self.bars) {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{close},{}",
+                timestamp.to_rfc3339(),
+                bar.open,
+                bar.high,
+                bar.low,
+                bar.close,
+                bar.volume
+            )
+            .expect("formatting into a String");
+        }
+        csv
+    }
+
+    ///
+    /// Collapse runs of identical consecutive closes, e.g. repeated stale
+    /// bars, into their first entry. Stale bars repeat the exact close, so
+    /// closes are compared exactly.
+    ///
+    #[allow(clippy::float_cmp)]
+    pub fn dedup_closes(&mut self) {
+        let keep: Vec<bool> = (0..self.closes.len())
+            .map(|i| i == 0 || self.closes[i] != self.closes[i - 1])
+            .collect();
+        let mut flags = keep.iter();
+        self.timestamps.retain(|_| *flags.next().unwrap());
+        let mut flags = keep.iter();
+        self.closes.retain(|_| *flags.next().unwrap());
+        let mut flags = keep.iter();
+        self.bars.retain(|_| *flags.next().unwrap());
+    }
+}
+
+///
+/// Parse newline-separated closes, skipping blank lines.
+///
+/// # Errors
+///
+/// An `InvalidData` error naming the first line that isn't a number.
+///
+pub fn parse_closes(input: &str) -> io::Result<Vec<f64>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.trim().parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {}: '{}' is not a number", i + 1, line.trim()),
+                )
+            })
+        })
+        .collect()
+}
+
+///
+/// Read the closes of each file in `dir`, keyed by the file's name as a
+/// symbol, e.g. `AAPL` or `^GSPC`.
+///
+/// # Errors
+///
+/// Any error reading the directory, or an `InvalidData` error naming the
+/// file and first line that isn't a number.
+///
+pub fn read_closes_dir(dir: &Path) -> io::Result<HashMap<String, Vec<f64>>> {
+    let mut closes = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let symbol = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "file name isn't a symbol"))?
+            .to_uppercase();
+        let parsed = parse_closes(&fs::read_to_string(&path)?)
+            .map_err(|err| Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        closes.insert(symbol, parsed);
+    }
+    Ok(closes)
+}
+
+#[cfg(test)]
+pub mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+
+    ///
+    /// A quote where all prices are `close`.
+    ///
+    pub fn quote(timestamp: u64, close: f64) -> yahoo::Quote {
+        yahoo::Quote {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            volume: 1000,
+            close,
+            adjclose: close,
+        }
+    }
+
+    #[test]
+    fn test_Series_from_quotes() {
+        let series = Series::from_quotes(vec![
+            quote(1_636_070_400, 3.0),
+            quote(1_635_897_600, 1.0),
+            quote(1_635_984_000, 2.0),
+        ]);
+        assert_eq!(
+            series.timestamps,
+            vec![
+                Utc.ymd(2021, 11, 3).and_hms(0, 0, 0),
+                Utc.ymd(2021, 11, 4).and_hms(0, 0, 0),
+                Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+            ]
+        );
+        assert_eq!(series.closes, vec![1.0, 2.0, 3.0]);
+        let bar_closes: Vec<_> = series.bars.iter().map(|b| b.close).collect();
+        assert_eq!(bar_closes, series.closes);
+        assert!(Series::from_quotes(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_Series_dedup_closes() {
+        let mut series = Series::from_quotes(vec![
+            quote(1_635_897_600, 1.0),
+            quote(1_635_984_000, 2.0),
+            quote(1_636_070_400, 2.0),
+            quote(1_636_156_800, 2.0),
+            quote(1_636_243_200, 1.0),
+        ]);
+        series.dedup_closes();
+        assert_eq!(series.closes, vec![1.0, 2.0, 1.0]);
+        assert_eq!(
+            series.timestamps,
+            vec![
+                Utc.ymd(2021, 11, 3).and_hms(0, 0, 0),
+                Utc.ymd(2021, 11, 4).and_hms(0, 0, 0),
+                Utc.ymd(2021, 11, 7).and_hms(0, 0, 0),
+            ]
+        );
+        assert_eq!(series.bars.len(), 3);
+
+        let mut empty = Series::default();
+        empty.dedup_closes();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_parse_closes() {
+        assert_eq!(
+            parse_closes("1\n2.5\n\n 3 \n").unwrap(),
+            vec![1.0, 2.5, 3.0]
+        );
+        let err = parse_closes("1\n\nabc\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "line 3: 'abc' is not a number");
+
+        let start = Utc.ymd(2021, 11, 1).and_hms(0, 0, 0);
+        let series = Series::from_closes(vec![1.0, 2.0], start);
+        assert_eq!(
+            series.timestamps,
+            vec![start, Utc.ymd(2021, 11, 2).and_hms(0, 0, 0)]
+        );
+        let bar_closes: Vec<_> = series.bars.iter().map(|b| b.close).collect();
+        assert_eq!(bar_closes, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_read_closes_dir() {
+        let dir = crate::sink::tests::temp_dir("closes-dir");
+        fs::write(dir.join("AAPL"), "1\n2\n").unwrap();
+        fs::write(dir.join("^gspc"), "3\n").unwrap();
+        let closes = read_closes_dir(&dir).unwrap();
+        assert_eq!(
+            closes,
+            HashMap::from([
+                ("AAPL".to_string(), vec![1.0, 2.0]),
+                ("^GSPC".to_string(), vec![3.0]),
+            ])
+        );
+        fs::write(dir.join("MSFT"), "x\n").unwrap();
+        let err = read_closes_dir(&dir).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .ends_with("MSFT: line 1: 'x' is not a number"));
+    }
+}
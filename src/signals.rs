@@ -0,0 +1,2717 @@
+//!
+//! Signals calculated on a series of closing prices.
+//!
+
+///
+/// A trait to provide a common interface for all signal calculations.
+///
+pub trait AsyncStockSignal {
+    ///
+    /// The signal's data type.
+    ///
+    type SignalType;
+
+    ///
+    /// Calculate the signal on the provided series.
+    ///
+    /// # Returns
+    ///
+    /// The signal (using the provided type) or `None` on error/invalid data.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType>;
+}
+
+///
+/// The relative change from each close to the next.
+///
+pub fn daily_returns(series: &[f64]) -> Vec<f64> {
+    series
+        .windows(2)
+        .map(|w| {
+            if w[0] == 0.0 {
+                0.0
+            } else {
+                (w[1] - w[0]) / w[0]
+            }
+        })
+        .collect()
+}
+
+///
+/// The arithmetic mean of the series, `None` if it's empty.
+///
+#[allow(dead_code)]
+pub fn mean(series: &[f64]) -> Option<f64> {
+    if series.is_empty() {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    Some(series.iter().sum::<f64>() / series.len() as f64)
+}
+
+///
+/// The logarithmic change from each close to the next, `0.0` where either
+/// close isn't positive.
+///
+#[allow(dead_code)]
+pub fn log_returns(series: &[f64]) -> Vec<f64> {
+    series
+        .windows(2)
+        .map(|w| {
+            if w[0] > 0.0 && w[1] > 0.0 {
+                (w[1] / w[0]).ln()
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+///
+/// The exponential moving average with smoothing `2 / (period + 1)`, seeded
+/// with the mean of the first `period` values. The first value is the seed,
+/// so there are `len - period + 1` of them, or `None` for fewer values than
+/// `period` or a `period` of zero.
+///
+#[allow(dead_code)]
+pub fn ema(series: &[f64], period: usize) -> Option<Vec<f64>> {
+    if period == 0 || series.len() < period {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut avg = mean(&series[..period])?;
+    let mut values = vec![avg];
+    for value in &series[period..] {
+        avg += alpha * (value - avg);
+        values.push(avg);
+    }
+    Some(values)
+}
+
+///
+/// The linearly weighted moving average over `period` values, weighting the
+/// latest value `period` times and the oldest one once. There are
+/// `len - period + 1` of them, or `None` for fewer values than `period` or a
+/// `period` of zero.
+///
+#[allow(dead_code)]
+pub fn wma(series: &[f64], period: usize) -> Option<Vec<f64>> {
+    if period == 0 || series.len() < period {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let total = (period * (period + 1) / 2) as f64;
+    Some(
+        series
+            .windows(period)
+            .map(|window| {
+                #[allow(clippy::cast_precision_loss)]
+                let sum: f64 = window
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| (i + 1) as f64 * value)
+                    .sum();
+                sum / total
+            })
+            .collect(),
+    )
+}
+
+///
+/// The stochastic %K of each value over the `period` values up to it, 0 at
+/// the low and 100 at the high of the window. A window without a range keeps
+/// the previous value (or starts at 50). `None` for fewer values than
+/// `period` or a `period` of zero.
+///
+#[allow(dead_code)]
+fn stochastic(series: &[f64], period: usize) -> Option<Vec<f64>> {
+    if period == 0 || series.len() < period {
+        return None;
+    }
+    let mut k = 50.0;
+    series
+        .windows(period)
+        .map(|window| {
+            let (min, max) = (MinPrice.calculate(window)?, MaxPrice.calculate(window)?);
+            if max > min {
+                k = (window[window.len() - 1] - min) / (max - min) * 100.0;
+            }
+            Some(k)
+        })
+        .collect()
+}
+
+///
+/// The stochastic %K smoothed by moving half way towards each new value.
+///
+#[allow(dead_code)]
+fn smoothed_stochastic(series: &[f64], period: usize) -> Option<Vec<f64>> {
+    let mut smoothed: Option<f64> = None;
+    Some(
+        stochastic(series, period)?
+            .into_iter()
+            .map(|k| {
+                let value = smoothed.map_or(k, |prev| prev + 0.5 * (k - prev));
+                smoothed = Some(value);
+                value
+            })
+            .collect(),
+    )
+}
+
+///
+/// Calculate a series signal over `seed` followed by `series` but only keep
+/// the values of the bars of `series`, so it's warmed up from the first one.
+///
+pub fn seeded<T>(
+    seed: &[f64],
+    series: &[f64],
+    signal: impl Fn(&[f64]) -> Option<Vec<T>>,
+) -> Option<Vec<T>> {
+    let mut values = signal(&[seed, series].concat())?;
+    values.drain(..values.len().saturating_sub(series.len()));
+    Some(values)
+}
+
+///
+/// The symbol's average daily return on the days the benchmark rose
+/// (`up == true`) or fell, relative to the benchmark's average return on those
+/// days. Both series are aligned at their last closes and only their overlap
+/// is compared.
+///
+#[allow(dead_code)]
+fn capture(series: &[f64], benchmark: &[f64], up: bool) -> Option<f64> {
+    let overlap = series.len().min(benchmark.len());
+    let returns = daily_returns(&series[series.len() - overlap..]);
+    let benchmark_returns = daily_returns(&benchmark[benchmark.len() - overlap..]);
+    let days: Vec<_> = returns
+        .iter()
+        .zip(&benchmark_returns)
+        .filter(|(_, b)| if up { **b > 0.0 } else { **b < 0.0 })
+        .collect();
+    if days.is_empty() {
+        return None;
+    }
+    let (sum, benchmark_sum) = days
+        .iter()
+        .fold((0.0, 0.0), |(sum, benchmark_sum), (r, b)| {
+            (sum + *r, benchmark_sum + *b)
+        });
+    // the averages are over the same days, so the sums have the same ratio
+    Some(sum / benchmark_sum)
+}
+
+///
+/// The upside capture ratio against a benchmark, `None` without days on
+/// which the benchmark rose. Annualizing both averages doesn't change it.
+///
+#[allow(dead_code)]
+pub fn upside_capture(series: &[f64], benchmark: &[f64]) -> Option<f64> {
+    capture(series, benchmark, true)
+}
+
+///
+/// The downside capture ratio against a benchmark, `None` without days on
+/// which the benchmark fell.
+///
+#[allow(dead_code)]
+pub fn downside_capture(series: &[f64], benchmark: &[f64]) -> Option<f64> {
+    capture(series, benchmark, false)
+}
+
+///
+/// Whether the latest close broke out of the range of the preceding closes.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub enum Extreme {
+    NewHigh,
+    NewLow,
+    Neither,
+}
+
+///
+/// Whether the latest extreme of the price is confirmed by its momentum.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub enum Divergence {
+    /// A lower low of the price with a higher low of the RSI
+    Bullish,
+    /// A higher high of the price with a lower high of the RSI
+    Bearish,
+    Neither,
+}
+
+pub struct PriceDifference;
+pub struct MinPrice;
+pub struct MaxPrice;
+pub struct WindowedSMA {
+    pub window_size: usize,
+}
+///
+/// Signals return the index of the extreme close rather than its timestamp:
+/// signals only ever see the closing prices, so the index is looked up in the
+/// timestamps aligned with the series to report the date.
+///
+#[allow(dead_code)]
+pub struct ArgMaxPrice;
+#[allow(dead_code)]
+pub struct ArgMinPrice;
+#[allow(dead_code)]
+pub struct TrailingReturn {
+    pub window: usize,
+}
+#[allow(dead_code)]
+pub struct SmaDeviation {
+    pub window_size: usize,
+}
+#[allow(dead_code)]
+pub struct NewExtreme {
+    pub lookback: usize,
+}
+#[allow(dead_code)]
+pub struct EwmaVolatility {
+    pub lambda: f64,
+}
+#[allow(dead_code)]
+pub struct UlcerIndex;
+#[allow(dead_code)]
+pub struct PercentDaysAboveSma {
+    pub window_size: usize,
+}
+#[allow(dead_code)]
+pub struct CurrentDrawdown;
+#[allow(dead_code)]
+pub struct MaxDrawdown;
+///
+/// Annualized signals assume one close per period with `periods_per_year`
+/// periods, e.g. 252 for daily closes of trading days.
+///
+#[allow(dead_code)]
+pub struct AnnualizedReturn {
+    pub periods_per_year: f64,
+}
+#[allow(dead_code)]
+pub struct CalmarRatio {
+    pub periods_per_year: f64,
+}
+#[allow(dead_code)]
+pub struct RollingSharpe {
+    pub window: usize,
+    pub risk_free_daily: f64,
+}
+#[allow(dead_code)]
+pub struct RealizedVolatility {
+    pub periods_per_year: f64,
+}
+#[allow(dead_code)]
+pub struct RangePercent;
+#[allow(dead_code)]
+pub struct CaptureRatios {
+    pub benchmark: Vec<f64>,
+}
+#[allow(dead_code)]
+pub struct RelativeStrengthIndex {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct RsiDivergence {
+    pub rsi_period: usize,
+    pub lookback: usize,
+}
+#[allow(dead_code)]
+pub struct BollingerBands {
+    pub window_size: usize,
+    pub num_std: f64,
+}
+#[allow(dead_code)]
+pub struct BollingerTouches {
+    pub window_size: usize,
+    pub num_std: f64,
+}
+#[allow(dead_code)]
+pub struct Aroon {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct WinRate;
+#[allow(dead_code)]
+pub struct DetrendedPriceOscillator {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct KaufmanAma {
+    pub period: usize,
+    pub fast: usize,
+    pub slow: usize,
+}
+#[allow(dead_code)]
+pub struct SmaSlope {
+    pub window_size: usize,
+    pub lookback: usize,
+}
+#[allow(dead_code)]
+pub struct FisherTransform {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct PercentagePriceOscillator {
+    pub fast: usize,
+    pub slow: usize,
+    pub signal: usize,
+}
+#[allow(dead_code)]
+pub struct RocPercentile {
+    pub roc_lag: usize,
+    pub lookback: usize,
+}
+#[allow(dead_code)]
+pub struct ChandeMomentumOscillator {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct InstantaneousTrendline {
+    pub alpha: f64,
+}
+#[allow(dead_code)]
+pub struct ConnorsRsi {
+    pub rsi_period: usize,
+    pub streak_rsi_period: usize,
+    pub roc_lookback: usize,
+}
+#[allow(dead_code)]
+pub struct TrendIntensityIndex {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct DisparityIndex {
+    pub period: usize,
+}
+#[allow(dead_code)]
+pub struct StochasticRsi {
+    pub rsi_period: usize,
+    pub stoch_period: usize,
+    pub k_smooth: usize,
+    pub d_smooth: usize,
+}
+#[allow(dead_code)]
+pub struct CoppockCurve {
+    pub roc1: usize,
+    pub roc2: usize,
+    pub wma: usize,
+}
+#[allow(dead_code)]
+pub struct SchaffTrendCycle {
+    pub macd_fast: usize,
+    pub macd_slow: usize,
+    pub cycle: usize,
+}
+#[allow(dead_code)]
+pub struct MaSpread {
+    pub fast: usize,
+    pub slow: usize,
+}
+#[allow(dead_code)]
+pub struct TrueStrengthIndex {
+    pub long: usize,
+    pub short: usize,
+}
+
+impl Default for TrueStrengthIndex {
+    fn default() -> Self {
+        TrueStrengthIndex {
+            long: 25,
+            short: 13,
+        }
+    }
+}
+
+impl AsyncStockSignal for PriceDifference {
+    type SignalType = (f64, f64);
+
+    ///
+    /// Calculates the absolute and relative difference between the beginning
+    /// and ending of an f64 series. The relative difference is relative to the
+    /// beginning.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(absolute, relative)` difference.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.is_empty() {
+            return None;
+        }
+        // unwrap is safe here even if first == last
+        let (first, last) = (series.first().unwrap(), series.last().unwrap());
+        let abs_diff = last - first;
+        let first = if *first == 0.0 { 1.0 } else { *first };
+        let rel_diff = abs_diff / first;
+        Some((abs_diff, rel_diff))
+    }
+}
+
+impl AsyncStockSignal for MinPrice {
+    type SignalType = f64;
+
+    ///
+    /// Find the minimum in a series of f64
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.is_empty() {
+            None
+        } else {
+            Some(series.iter().fold(f64::MAX, |acc, q| acc.min(*q)))
+        }
+    }
+}
+
+impl AsyncStockSignal for MaxPrice {
+    type SignalType = f64;
+
+    ///
+    /// Find the maximum in a series of f64
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if series.is_empty() {
+            None
+        } else {
+            Some(series.iter().fold(f64::MIN, |acc, q| acc.max(*q)))
+        }
+    }
+}
+
+impl AsyncStockSignal for ArgMaxPrice {
+    type SignalType = usize;
+
+    ///
+    /// Find the index of the maximum in a series of f64. Ties resolve to the
+    /// first occurrence.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        series
+            .iter()
+            .enumerate()
+            .fold(None, |acc: Option<(usize, f64)>, (i, q)| match acc {
+                Some((_, max)) if max >= *q => acc,
+                _ => Some((i, *q)),
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+impl AsyncStockSignal for ArgMinPrice {
+    type SignalType = usize;
+
+    ///
+    /// Find the index of the minimum in a series of f64. Ties resolve to the
+    /// first occurrence.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        series
+            .iter()
+            .enumerate()
+            .fold(None, |acc: Option<(usize, f64)>, (i, q)| match acc {
+                Some((_, min)) if min <= *q => acc,
+                _ => Some((i, *q)),
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+impl AsyncStockSignal for WindowedSMA {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Window function to create a simple moving average
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if !series.is_empty() && self.window_size > 1 {
+            #[allow(clippy::cast_precision_loss)]
+            Some(
+                series
+                    .windows(self.window_size)
+                    .map(|w| w.iter().sum::<f64>() / w.len() as f64)
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+impl AsyncStockSignal for TrailingReturn {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The relative return from `window` bars earlier to each bar, starting
+    /// at the first bar with enough history. Like `PriceDifference`, a zero
+    /// base price is treated as `1.0`.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.window == 0 || series.len() <= self.window {
+            return None;
+        }
+        Some(
+            series
+                .windows(self.window + 1)
+                .map(|w| {
+                    let (first, last) = (w[0], w[self.window]);
+                    let base = if first == 0.0 { 1.0 } else { first };
+                    (last - first) / base
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for SmaDeviation {
+    type SignalType = f64;
+
+    ///
+    /// The relative distance of the last close from the last simple moving
+    /// average: `(last - sma_last) / sma_last`, positive above the average.
+    ///
+    /// # Returns
+    ///
+    /// The distance or `None` when the moving average can't be calculated or
+    /// is zero.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.window_size,
+        }
+        .calculate(series)?;
+        let (last, sma_last) = (series.last()?, sma.last()?);
+        if *sma_last == 0.0 {
+            return None;
+        }
+        Some((last - sma_last) / sma_last)
+    }
+}
+
+impl AsyncStockSignal for NewExtreme {
+    type SignalType = Extreme;
+
+    ///
+    /// Compare the latest close to the `lookback` closes before it. Only a
+    /// close strictly above (below) all of them is a new high (low), so
+    /// matching the prior high or low is `Neither`.
+    ///
+    /// # Returns
+    ///
+    /// The kind of extreme or `None` with fewer than `lookback + 1` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.lookback == 0 || series.len() <= self.lookback {
+            return None;
+        }
+        let (last, previous) = series.split_last()?;
+        let previous = &previous[previous.len() - self.lookback..];
+        let high = MaxPrice.calculate(previous)?;
+        let low = MinPrice.calculate(previous)?;
+        Some(if *last > high {
+            Extreme::NewHigh
+        } else if *last < low {
+            Extreme::NewLow
+        } else {
+            Extreme::Neither
+        })
+    }
+}
+
+impl AsyncStockSignal for EwmaVolatility {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The exponentially-weighted volatility of daily returns with decay
+    /// `lambda` (`RiskMetrics` uses 0.94):
+    /// `var_t = lambda * var_{t-1} + (1 - lambda) * r_t^2`, seeded with the
+    /// first squared return.
+    ///
+    /// # Returns
+    ///
+    /// The volatility after each daily return, or `None` with fewer than two
+    /// prices or a `lambda` outside of (0, 1).
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if !(self.lambda > 0.0 && self.lambda < 1.0) {
+            return None;
+        }
+        let returns = daily_returns(series);
+        let first = returns.first()?;
+        let mut var = first * first;
+        Some(
+            returns
+                .iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    if i > 0 {
+                        var = self.lambda * var + (1.0 - self.lambda) * r * r;
+                    }
+                    var.sqrt()
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for UlcerIndex {
+    type SignalType = f64;
+
+    ///
+    /// The root mean square of the percentage drawdowns from the running peak,
+    /// which grows with both the depth and the duration of drawdowns. A peak
+    /// of zero counts as no drawdown.
+    ///
+    /// # Returns
+    ///
+    /// The index (`0.0` for a series that never falls) or `None` for an empty
+    /// series.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let mut peak = *series.first()?;
+        let sum_squares: f64 = series
+            .iter()
+            .map(|close| {
+                peak = peak.max(*close);
+                let drawdown = if peak > 0.0 {
+                    (close - peak) / peak * 100.0
+                } else {
+                    0.0
+                };
+                drawdown * drawdown
+            })
+            .sum();
+        #[allow(clippy::cast_precision_loss)]
+        Some((sum_squares / series.len() as f64).sqrt())
+    }
+}
+
+impl AsyncStockSignal for PercentDaysAboveSma {
+    type SignalType = f64;
+
+    ///
+    /// The percentage (0 to 100) of closes strictly above their simple moving
+    /// average, counting only the closes from `window_size - 1` on where the
+    /// average is defined.
+    ///
+    /// # Returns
+    ///
+    /// The percentage or `None` with fewer closes than the window.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.window_size,
+        }
+        .calculate(series)?;
+        if sma.is_empty() {
+            return None;
+        }
+        let above = series[self.window_size - 1..]
+            .iter()
+            .zip(&sma)
+            .filter(|(close, avg)| close > avg)
+            .count();
+        #[allow(clippy::cast_precision_loss)]
+        Some(above as f64 / sma.len() as f64 * 100.0)
+    }
+}
+
+impl AsyncStockSignal for CurrentDrawdown {
+    type SignalType = f64;
+
+    ///
+    /// How far the last close is below the highest close of the series,
+    /// relative to that peak: `(last - peak) / peak`. A peak of zero counts
+    /// as no drawdown.
+    ///
+    /// # Returns
+    ///
+    /// The non-positive drawdown (`0.0` if the last close is the peak) or
+    /// `None` for an empty series.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let last = *series.last()?;
+        let peak = MaxPrice.calculate(series)?;
+        if peak > 0.0 {
+            Some((last - peak) / peak)
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+impl AsyncStockSignal for MaxDrawdown {
+    type SignalType = f64;
+
+    ///
+    /// The largest decline from a running peak to a later close relative to
+    /// that peak. Peaks of zero count as no drawdown.
+    ///
+    /// # Returns
+    ///
+    /// The non-positive drawdown (`0.0` for a series that never falls) or
+    /// `None` for an empty series.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let mut peak = *series.first()?;
+        Some(series.iter().fold(0.0, |max_drawdown: f64, close| {
+            peak = peak.max(*close);
+            if peak > 0.0 {
+                max_drawdown.min((close - peak) / peak)
+            } else {
+                max_drawdown
+            }
+        }))
+    }
+}
+
+impl AsyncStockSignal for AnnualizedReturn {
+    type SignalType = f64;
+
+    ///
+    /// The compound annual growth rate between the first and last close:
+    /// `(last / first)^(periods_per_year / (n - 1)) - 1`.
+    ///
+    /// # Returns
+    ///
+    /// The rate or `None` with fewer than two closes, a first close that
+    /// isn't positive or a non-positive number of periods per year.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let (first, last) = (*series.first()?, *series.last()?);
+        if series.len() < 2 || first <= 0.0 || self.periods_per_year <= 0.0 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let years = (series.len() - 1) as f64 / self.periods_per_year;
+        Some((last / first).powf(1.0 / years) - 1.0)
+    }
+}
+
+impl AsyncStockSignal for CalmarRatio {
+    type SignalType = f64;
+
+    ///
+    /// The annualized return per unit of maximum drawdown:
+    /// `annualized_return / |max_drawdown|`.
+    ///
+    /// # Returns
+    ///
+    /// The ratio or `None` if either is undefined or there was no drawdown.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let max_drawdown = MaxDrawdown.calculate(series)?;
+        if max_drawdown == 0.0 {
+            return None;
+        }
+        let annualized = AnnualizedReturn {
+            periods_per_year: self.periods_per_year,
+        }
+        .calculate(series)?;
+        Some(annualized / max_drawdown.abs())
+    }
+}
+
+impl AsyncStockSignal for RollingSharpe {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The (daily, not annualized) Sharpe ratio of each rolling window of
+    /// `window` daily returns: the mean excess return over `risk_free_daily`
+    /// divided by the sample standard deviation of the excess returns.
+    /// Windows without variance (below `1e-12`) emit `0.0`.
+    ///
+    /// # Returns
+    ///
+    /// One ratio per window, ending at each return from the `window`th on, or
+    /// `None` with a `window` of at most one or fewer returns than `window`.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let returns = daily_returns(series);
+        if self.window <= 1 || returns.len() < self.window {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.window as f64;
+        Some(
+            returns
+                .windows(self.window)
+                .map(|w| {
+                    let mean = w.iter().map(|r| r - self.risk_free_daily).sum::<f64>() / n;
+                    let var = w
+                        .iter()
+                        .map(|r| (r - self.risk_free_daily - mean).powi(2))
+                        .sum::<f64>()
+                        / (n - 1.0);
+                    let std = var.sqrt();
+                    if std < 1e-12 {
+                        0.0
+                    } else {
+                        mean / std
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for BollingerBands {
+    type SignalType = Vec<(f64, f64, f64)>;
+
+    ///
+    /// The simple moving average with bands `num_std` (population) standard
+    /// deviations of the window below and above it.
+    ///
+    /// # Returns
+    ///
+    /// `(lower, middle, upper)` for each window, ending at each close from
+    /// `window_size - 1` on, or `None` with a window of at most one or fewer
+    /// closes than the window.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.window_size,
+        }
+        .calculate(series)?;
+        if sma.is_empty() {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.window_size as f64;
+        Some(
+            series
+                .windows(self.window_size)
+                .zip(sma)
+                .map(|(w, mean)| {
+                    let std = (w.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / n).sqrt();
+                    (mean - self.num_std * std, mean, mean + self.num_std * std)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for BollingerTouches {
+    type SignalType = (usize, usize);
+
+    ///
+    /// Count the closes strictly above the upper and strictly below the lower
+    /// Bollinger band of the window they end.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(above, below)` or `None` when the bands can't be calculated.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let bands = BollingerBands {
+            window_size: self.window_size,
+            num_std: self.num_std,
+        }
+        .calculate(series)?;
+        Some(series[self.window_size - 1..].iter().zip(bands).fold(
+            (0, 0),
+            |(above, below), (close, (lower, _, upper))| {
+                if *close > upper {
+                    (above + 1, below)
+                } else if *close < lower {
+                    (above, below + 1)
+                } else {
+                    (above, below)
+                }
+            },
+        ))
+    }
+}
+
+impl RealizedVolatility {
+    ///
+    /// The realized variance: the sum of squared daily log returns, or `None`
+    /// with fewer than two closes.
+    ///
+    #[allow(dead_code)]
+    pub fn realized_variance(series: &[f64]) -> Option<f64> {
+        if series.len() < 2 {
+            return None;
+        }
+        Some(log_returns(series).iter().map(|r| r * r).sum())
+    }
+}
+
+impl AsyncStockSignal for RealizedVolatility {
+    type SignalType = f64;
+
+    ///
+    /// The annualized realized volatility,
+    /// `sqrt(realized_variance * periods_per_year)` (252 periods for daily
+    /// closes).
+    ///
+    /// # Returns
+    ///
+    /// The volatility or `None` with fewer than two closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        Some((Self::realized_variance(series)? * self.periods_per_year).sqrt())
+    }
+}
+
+impl AsyncStockSignal for RangePercent {
+    type SignalType = f64;
+
+    ///
+    /// The width of the trading range relative to the mean close:
+    /// `(max - min) / mean * 100`.
+    ///
+    /// # Returns
+    ///
+    /// The percentage or `None` for an empty series or a mean of zero.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let (min, max, mean) = (
+            MinPrice.calculate(series)?,
+            MaxPrice.calculate(series)?,
+            mean(series)?,
+        );
+        if mean == 0.0 {
+            return None;
+        }
+        Some((max - min) / mean * 100.0)
+    }
+}
+
+impl AsyncStockSignal for RelativeStrengthIndex {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Wilder's relative strength index: the average gain and loss of the
+    /// first `period` changes seed `avg = (avg * (period - 1) + change) / period`
+    /// and `RSI = 100 - 100 / (1 + avg_gain / avg_loss)`, which is `100`
+    /// without losses (`50` without any change).
+    ///
+    /// # Returns
+    ///
+    /// The index for each close from `period` on or `None` with a `period` of
+    /// zero or no more closes than `period`.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || series.len() <= self.period {
+            return None;
+        }
+        let rsi = |gain: f64, loss: f64| {
+            if loss == 0.0 {
+                if gain == 0.0 {
+                    50.0
+                } else {
+                    100.0
+                }
+            } else {
+                100.0 - 100.0 / (1.0 + gain / loss)
+            }
+        };
+        let changes: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        #[allow(clippy::cast_precision_loss)]
+        let period = self.period as f64;
+        let (mut gain, mut loss) = changes[..self.period]
+            .iter()
+            .fold((0.0, 0.0), |(gain, loss), c| {
+                (gain + c.max(0.0), loss + (-c).max(0.0))
+            });
+        gain /= period;
+        loss /= period;
+        let mut values = vec![rsi(gain, loss)];
+        for change in &changes[self.period..] {
+            gain = (gain * (period - 1.0) + change.max(0.0)) / period;
+            loss = (loss * (period - 1.0) + (-change).max(0.0)) / period;
+            values.push(rsi(gain, loss));
+        }
+        Some(values)
+    }
+}
+
+impl AsyncStockSignal for RsiDivergence {
+    type SignalType = Divergence;
+
+    ///
+    /// Compare the last close to the highest and lowest of the `lookback`
+    /// closes before it: a higher high with an RSI below the RSI at that high
+    /// is a bearish divergence, a lower low with an RSI above the RSI at that
+    /// low a bullish one.
+    ///
+    /// # Returns
+    ///
+    /// The divergence or `None` with a `lookback` of zero or fewer than
+    /// `rsi_period + lookback + 1` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let rsi = RelativeStrengthIndex {
+            period: self.rsi_period,
+        }
+        .calculate(series)?;
+        if self.lookback == 0 || rsi.len() <= self.lookback {
+            return None;
+        }
+        // the RSI starts at the close after its first `rsi_period` changes
+        let prices = &series[series.len() - self.lookback - 1..];
+        let rsi = &rsi[rsi.len() - self.lookback - 1..];
+        let (last_price, last_rsi) = (prices[self.lookback], rsi[self.lookback]);
+        let high = ArgMaxPrice.calculate(&prices[..self.lookback])?;
+        let low = ArgMinPrice.calculate(&prices[..self.lookback])?;
+        Some(if last_price > prices[high] && last_rsi < rsi[high] {
+            Divergence::Bearish
+        } else if last_price < prices[low] && last_rsi > rsi[low] {
+            Divergence::Bullish
+        } else {
+            Divergence::Neither
+        })
+    }
+}
+
+impl AsyncStockSignal for CaptureRatios {
+    type SignalType = (f64, f64);
+
+    ///
+    /// The upside and downside capture ratios against the benchmark's closes.
+    /// Above 1 the symbol moved more than the benchmark, so a defensive
+    /// symbol has a low downside capture.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(upside, downside)` or `None` if the benchmark didn't both
+    /// rise and fall during the overlap.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        Some((
+            upside_capture(series, &self.benchmark)?,
+            downside_capture(series, &self.benchmark)?,
+        ))
+    }
+}
+
+impl AsyncStockSignal for Aroon {
+    type SignalType = (f64, f64);
+
+    ///
+    /// How recently the last `period + 1` closes peaked and bottomed out:
+    /// `100 * (period - bars_since_high) / period` and the analogous down
+    /// value. Ties count as the most recent extreme.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(up, down)` or `None` for a `period` of zero or fewer than
+    /// `period + 1` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || series.len() <= self.period {
+            return None;
+        }
+        let window = &series[series.len() - self.period - 1..];
+        let bars_since = |newer: fn(f64, f64) -> bool| {
+            window
+                .iter()
+                .rev()
+                .enumerate()
+                .fold((0, window[window.len() - 1]), |(i, best), (j, q)| {
+                    if newer(*q, best) {
+                        (j, *q)
+                    } else {
+                        (i, best)
+                    }
+                })
+                .0
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let aroon = |since: usize| (self.period - since) as f64 / self.period as f64 * 100.0;
+        Some((
+            aroon(bars_since(|q, high| q > high)),
+            aroon(bars_since(|q, low| q < low)),
+        ))
+    }
+}
+
+impl AsyncStockSignal for TrueStrengthIndex {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The true strength index: the changes between closes smoothed by a
+    /// `long` and then a `short` EMA, divided by their absolute values
+    /// smoothed the same way and scaled by 100. Values are `0.0` while the
+    /// closes haven't moved at all.
+    ///
+    /// # Returns
+    ///
+    /// The last `len - long - short + 1` values, `None` for fewer than
+    /// `long + short` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let momentum: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        let absolute: Vec<f64> = momentum.iter().map(|m| m.abs()).collect();
+        let smoothed = ema(&ema(&momentum, self.long)?, self.short)?;
+        let smoothed_absolute = ema(&ema(&absolute, self.long)?, self.short)?;
+        Some(
+            smoothed
+                .iter()
+                .zip(&smoothed_absolute)
+                .map(|(m, a)| if *a < 1e-12 { 0.0 } else { m / a * 100.0 })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for WinRate {
+    type SignalType = f64;
+
+    ///
+    /// The percentage of daily returns that were strictly positive, so flat
+    /// days count as losses.
+    ///
+    /// # Returns
+    ///
+    /// The percentage or `None` with fewer than two prices.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let returns = daily_returns(series);
+        if returns.is_empty() {
+            return None;
+        }
+        let wins = returns.iter().filter(|r| **r > 0.0).count();
+        #[allow(clippy::cast_precision_loss)]
+        Some(wins as f64 / returns.len() as f64 * 100.0)
+    }
+}
+
+impl AsyncStockSignal for MaSpread {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The difference `fast_sma - slow_sma` of two simple moving averages
+    /// over their common trailing closes, positive while the fast one is
+    /// above.
+    ///
+    /// # Returns
+    ///
+    /// One difference per close both averages cover, or `None` if either
+    /// can't be calculated over the series.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let fast = WindowedSMA {
+            window_size: self.fast,
+        }
+        .calculate(series)?;
+        let slow = WindowedSMA {
+            window_size: self.slow,
+        }
+        .calculate(series)?;
+        let len = fast.len().min(slow.len());
+        if len == 0 {
+            return None;
+        }
+        Some(
+            fast[fast.len() - len..]
+                .iter()
+                .zip(&slow[slow.len() - len..])
+                .map(|(fast, slow)| fast - slow)
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for DetrendedPriceOscillator {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The close `period / 2 + 1` bars before each bar less the `period` bar
+    /// simple moving average ending at it, which removes trends longer than
+    /// the period and leaves the shorter cycles.
+    ///
+    /// # Returns
+    ///
+    /// One value per bar from the first one with both a full window and a
+    /// displaced close, or `None` if there's no such bar.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.period,
+        }
+        .calculate(series)?;
+        let shift = self.period / 2 + 1;
+        // sma[i] ends at bar i + period - 1, skip those without a displaced close
+        let skip = shift.saturating_sub(self.period - 1);
+        let values: Vec<f64> = sma
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(i, avg)| series[i + self.period - 1 - shift] - avg)
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values)
+    }
+}
+
+impl AsyncStockSignal for KaufmanAma {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Kaufman's adaptive moving average, seeded with the close at
+    /// `period - 1`. The efficiency ratio of each window of `period` changes,
+    /// `|net change| / sum of |changes|`, blends the smoothing constants of a
+    /// `fast` and a `slow` EMA: `sc = (er * (fast_sc - slow_sc) + slow_sc)^2`
+    /// and `kama += sc * (close - kama)`, so it follows trends and flattens
+    /// in noise. Windows without any change count as noise.
+    ///
+    /// # Returns
+    ///
+    /// One value per close from the seed on, or `None` for fewer than
+    /// `period + 1` closes or any length of zero.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || self.fast == 0 || self.slow == 0 || series.len() <= self.period {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let (fast_sc, slow_sc) = (
+            2.0 / (self.fast as f64 + 1.0),
+            2.0 / (self.slow as f64 + 1.0),
+        );
+        let mut kama = series[self.period - 1];
+        let mut values = vec![kama];
+        for window in series.windows(self.period + 1) {
+            let close = window[self.period];
+            let change = (close - window[0]).abs();
+            let volatility: f64 = window.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+            let er = if volatility > 0.0 {
+                change / volatility
+            } else {
+                0.0
+            };
+            let sc = (er * (fast_sc - slow_sc) + slow_sc).powi(2);
+            kama += sc * (close - kama);
+            values.push(kama);
+        }
+        Some(values)
+    }
+}
+
+impl AsyncStockSignal for SmaSlope {
+    type SignalType = f64;
+
+    ///
+    /// The average change per bar of the simple moving average over the last
+    /// `lookback` bars: `(sma_last - sma_{last - lookback}) / lookback`,
+    /// positive while the average rises.
+    ///
+    /// # Returns
+    ///
+    /// The slope or `None` for a `lookback` of zero or without `lookback + 1`
+    /// averages.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.window_size,
+        }
+        .calculate(series)?;
+        if self.lookback == 0 || sma.len() <= self.lookback {
+            return None;
+        }
+        let last = sma.len() - 1;
+        #[allow(clippy::cast_precision_loss)]
+        Some((sma[last] - sma[last - self.lookback]) / self.lookback as f64)
+    }
+}
+
+impl AsyncStockSignal for FisherTransform {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Ehlers' Fisher transform: each close's position in the range of the
+    /// last `period` closes is scaled to -1..1 and smoothed,
+    /// `x = 0.66 * (pos - 0.5) + 0.67 * x_prev`, clamped to ±0.999 and
+    /// transformed with `fish = 0.5 * ln((1 + x) / (1 - x)) + 0.5 * fish_prev`.
+    /// A flat window counts as the middle of its range.
+    ///
+    /// # Returns
+    ///
+    /// One value per bar from the first full window on, or `None` for a
+    /// `period` of zero or fewer closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || series.len() < self.period {
+            return None;
+        }
+        let (mut x, mut fish) = (0.0_f64, 0.0_f64);
+        series
+            .windows(self.period)
+            .map(|window| {
+                let (min, max) = (MinPrice.calculate(window)?, MaxPrice.calculate(window)?);
+                let close = window[window.len() - 1];
+                let position = if max > min {
+                    (close - min) / (max - min)
+                } else {
+                    0.5
+                };
+                x = (0.66 * (position - 0.5) + 0.67 * x).clamp(-0.999, 0.999);
+                fish = 0.5 * ((1.0 + x) / (1.0 - x)).ln() + 0.5 * fish;
+                Some(fish)
+            })
+            .collect()
+    }
+}
+
+impl AsyncStockSignal for PercentagePriceOscillator {
+    type SignalType = Vec<(f64, f64, f64)>;
+
+    ///
+    /// The difference of a `fast` and a `slow` EMA as a percentage of the
+    /// slow one, like MACD but independent of the price so it's comparable
+    /// across symbols, with a `signal` EMA of it and their difference.
+    ///
+    /// # Returns
+    ///
+    /// `(ppo, signal_line, histogram)` for each close the signal line covers,
+    /// or `None` if an EMA can't be calculated or the slow one is zero.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let fast = ema(series, self.fast)?;
+        let slow = ema(series, self.slow)?;
+        let len = fast.len().min(slow.len());
+        let ppo = fast[fast.len() - len..]
+            .iter()
+            .zip(&slow[slow.len() - len..])
+            .map(|(fast, slow)| {
+                if slow.abs() < 1e-12 {
+                    None
+                } else {
+                    Some(100.0 * (fast - slow) / slow)
+                }
+            })
+            .collect::<Option<Vec<f64>>>()?;
+        let signal = ema(&ppo, self.signal)?;
+        Some(
+            ppo[ppo.len() - signal.len()..]
+                .iter()
+                .zip(signal)
+                .map(|(ppo, signal)| (*ppo, signal, ppo - signal))
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for RocPercentile {
+    type SignalType = f64;
+
+    ///
+    /// The percentile rank of the latest rate of change over `roc_lag` bars
+    /// among the last `lookback` of them: the percentage of the earlier ones
+    /// that are strictly lower, so 100 is the strongest momentum of the
+    /// lookback and 0 the weakest (or tied with it).
+    ///
+    /// # Returns
+    ///
+    /// The rank or `None` with a `lookback` below two or fewer than
+    /// `roc_lag + lookback` prices.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.lookback < 2 {
+            return None;
+        }
+        let roc = TrailingReturn {
+            window: self.roc_lag,
+        }
+        .calculate(series)?;
+        if roc.len() < self.lookback {
+            return None;
+        }
+        let (latest, earlier) = roc[roc.len() - self.lookback..].split_last()?;
+        let below = earlier.iter().filter(|r| *r < latest).count();
+        #[allow(clippy::cast_precision_loss)]
+        Some(below as f64 / earlier.len() as f64 * 100.0)
+    }
+}
+
+impl AsyncStockSignal for SchaffTrendCycle {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The Schaff Trend Cycle: the MACD line of a `macd_fast` and a
+    /// `macd_slow` EMA put through a smoothed stochastic over `cycle` values
+    /// twice, an oscillator between 0 and 100 that turns sooner than MACD.
+    ///
+    /// # Returns
+    ///
+    /// The STC for each close after both stochastics warmed up or `None` if
+    /// there are too few closes for them.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let fast = ema(series, self.macd_fast)?;
+        let slow = ema(series, self.macd_slow)?;
+        let len = fast.len().min(slow.len());
+        let macd: Vec<f64> = fast[fast.len() - len..]
+            .iter()
+            .zip(&slow[slow.len() - len..])
+            .map(|(fast, slow)| fast - slow)
+            .collect();
+        smoothed_stochastic(&smoothed_stochastic(&macd, self.cycle)?, self.cycle)
+    }
+}
+
+impl AsyncStockSignal for CoppockCurve {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The Coppock Curve: the `wma` bar weighted moving average of the sum of
+    /// the rates of change over `roc1` and `roc2` bars, in percent. Meant for
+    /// monthly closes (classically 14, 11 and 10), where a turn up from below
+    /// zero is a long-term buy signal.
+    ///
+    /// # Returns
+    ///
+    /// The curve for each close it covers or `None` if there are too few
+    /// closes for the longer rate of change and the average.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let roc1 = TrailingReturn { window: self.roc1 }.calculate(series)?;
+        let roc2 = TrailingReturn { window: self.roc2 }.calculate(series)?;
+        let len = roc1.len().min(roc2.len());
+        let sum: Vec<f64> = roc1[roc1.len() - len..]
+            .iter()
+            .zip(&roc2[roc2.len() - len..])
+            .map(|(roc1, roc2)| 100.0 * (roc1 + roc2))
+            .collect();
+        wma(&sum, self.wma)
+    }
+}
+
+impl AsyncStockSignal for ChandeMomentumOscillator {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Chande's momentum oscillator over each window of `period` changes:
+    /// `100 * (gains - losses) / (gains + losses)`, from -100 when every
+    /// close fell to 100 when every close rose. Unlike RSI it isn't smoothed.
+    /// A window without any change is 0.
+    ///
+    /// # Returns
+    ///
+    /// The oscillator for each close with `period` changes before it or
+    /// `None` with a `period` of zero or fewer than `period + 1` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.period == 0 || series.len() <= self.period {
+            return None;
+        }
+        let changes: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        Some(
+            changes
+                .windows(self.period)
+                .map(|window| {
+                    let up: f64 = window.iter().filter(|c| **c > 0.0).sum();
+                    let down: f64 = -window.iter().filter(|c| **c < 0.0).sum::<f64>();
+                    if up + down > 0.0 {
+                        100.0 * (up - down) / (up + down)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for InstantaneousTrendline {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Ehlers' instantaneous trendline, a two-pole filter of the closes that
+    /// follows a straight trend without lag:
+    ///
+    /// `it = (α - α²/4) p + α²/2 p₁ - (α - 3α²/4) p₂ + 2(1 - α) it₁ - (1 - α)² it₂`
+    ///
+    /// The first two values are the closes themselves.
+    ///
+    /// # Returns
+    ///
+    /// The trendline for every close or `None` with fewer than three closes
+    /// or an `alpha` outside `(0, 1)`.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let a = self.alpha;
+        if series.len() < 3 || a <= 0.0 || a >= 1.0 {
+            return None;
+        }
+        let mut trend = series[..2].to_vec();
+        for i in 2..series.len() {
+            let value = (a - a * a / 4.0) * series[i] + a * a / 2.0 * series[i - 1]
+                - (a - 0.75 * a * a) * series[i - 2]
+                + 2.0 * (1.0 - a) * trend[i - 1]
+                - (1.0 - a).powi(2) * trend[i - 2];
+            trend.push(value);
+        }
+        Some(trend)
+    }
+}
+
+impl AsyncStockSignal for ConnorsRsi {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// Connors RSI, the average of three components for each close: the RSI
+    /// of the closes over `rsi_period`, the RSI over `streak_rsi_period` of
+    /// the streak length (the number of consecutive up closes, negative for
+    /// down closes and zero on an unchanged close) and the percentile rank of
+    /// the latest one-bar rate of change among the `roc_lookback` before it.
+    ///
+    /// # Returns
+    ///
+    /// The index from the first close all three components cover or `None`
+    /// with a zero period or lookback or too few closes for any component.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        if self.roc_lookback == 0 {
+            return None;
+        }
+        let price = RelativeStrengthIndex {
+            period: self.rsi_period,
+        }
+        .calculate(series)?;
+        let mut streaks: Vec<f64> = vec![0.0];
+        for w in series.windows(2) {
+            let last = streaks[streaks.len() - 1];
+            streaks.push(if w[1] > w[0] {
+                last.max(0.0) + 1.0
+            } else if w[1] < w[0] {
+                last.min(0.0) - 1.0
+            } else {
+                0.0
+            });
+        }
+        let streak = RelativeStrengthIndex {
+            period: self.streak_rsi_period,
+        }
+        .calculate(&streaks)?;
+        let rank = RocPercentile {
+            roc_lag: 1,
+            lookback: self.roc_lookback + 1,
+        };
+        let start = self
+            .rsi_period
+            .max(self.streak_rsi_period)
+            .max(self.roc_lookback + 1);
+        if start >= series.len() {
+            return None;
+        }
+        (start..series.len())
+            .map(|i| {
+                let rank = rank.calculate(&series[..=i])?;
+                Some((price[i - self.rsi_period] + streak[i - self.streak_rsi_period] + rank) / 3.0)
+            })
+            .collect()
+    }
+}
+
+impl AsyncStockSignal for TrendIntensityIndex {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The trend intensity index: the deviations of the closes from their
+    /// `period` bar simple moving average, summed over the last `period` bars
+    /// as `100 * up / (up + down)`. Above 80 marks a strong uptrend, below 20
+    /// a strong downtrend and without any deviation it's `50`.
+    ///
+    /// # Returns
+    ///
+    /// One value per bar from the first with `period` deviations or `None`
+    /// with a `period` below two or fewer than `2 * period - 1` closes.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.period,
+        }
+        .calculate(series)?;
+        // sma[i] ends at bar i + period - 1
+        let deviations: Vec<f64> = sma
+            .iter()
+            .zip(&series[self.period - 1..])
+            .map(|(avg, close)| close - avg)
+            .collect();
+        if deviations.len() < self.period {
+            return None;
+        }
+        Some(
+            deviations
+                .windows(self.period)
+                .map(|w| {
+                    let up: f64 = w.iter().map(|d| d.max(0.0)).sum();
+                    let down: f64 = w.iter().map(|d| (-d).max(0.0)).sum();
+                    if up + down == 0.0 {
+                        50.0
+                    } else {
+                        100.0 * up / (up + down)
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl AsyncStockSignal for DisparityIndex {
+    type SignalType = Vec<f64>;
+
+    ///
+    /// The disparity index, the percentage distance of each close from the
+    /// `period` bar simple moving average ending at it:
+    /// `100 * (close - sma) / sma`, positive above the average.
+    ///
+    /// # Returns
+    ///
+    /// One value per bar from the first full window or `None` when the moving
+    /// average can't be calculated, is empty or is zero anywhere.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let sma = WindowedSMA {
+            window_size: self.period,
+        }
+        .calculate(series)?;
+        if sma.is_empty() {
+            return None;
+        }
+        // sma[i] ends at bar i + period - 1
+        sma.iter()
+            .zip(&series[self.period - 1..])
+            .map(|(avg, close)| (*avg != 0.0).then(|| 100.0 * (close - avg) / avg))
+            .collect()
+    }
+}
+
+impl AsyncStockSignal for StochasticRsi {
+    type SignalType = Vec<(f64, f64)>;
+
+    ///
+    /// The stochastic oscillator applied to the RSI over `rsi_period`: where
+    /// the RSI is within its range of the last `stoch_period` values, from 0
+    /// at its low to 100 at its high, as %K smoothed by a `k_smooth` simple
+    /// moving average and %D, its `d_smooth` average. A window in which the
+    /// RSI didn't move keeps the previous value (or starts at 50). Smoothing
+    /// over a single value leaves the values as they are.
+    ///
+    /// # Returns
+    ///
+    /// `(k, d)` for each close %D covers or `None` with a zero period or too
+    /// few closes for any step.
+    ///
+    fn calculate(&self, series: &[f64]) -> Option<Self::SignalType> {
+        let smooth = |values: &[f64], period: usize| match period {
+            0 => None,
+            1 => Some(values.to_vec()),
+            _ => WindowedSMA {
+                window_size: period,
+            }
+            .calculate(values)
+            .filter(|sma| !sma.is_empty()),
+        };
+        let rsi = RelativeStrengthIndex {
+            period: self.rsi_period,
+        }
+        .calculate(series)?;
+        let k = smooth(&stochastic(&rsi, self.stoch_period)?, self.k_smooth)?;
+        let d = smooth(&k, self.d_smooth)?;
+        Some(k[k.len() - d.len()..].iter().copied().zip(d).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::approx::{
+        assert_all_approx_eq, assert_approx_eq, assert_pair_approx_eq, assert_series_approx_eq,
+        assert_some_approx_eq,
+    };
+
+    #[test]
+    fn test_daily_returns() {
+        assert!(daily_returns(&[]).is_empty());
+        assert!(daily_returns(&[1.0]).is_empty());
+        assert_all_approx_eq(
+            &daily_returns(&[1.0, 2.0, 1.0, 0.0, 4.0]),
+            &[1.0, -0.5, -1.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_PriceDifference_calculate() {
+        let signal = PriceDifference {};
+        assert_eq!(signal.calculate(&[]), None);
+        assert_pair_approx_eq(signal.calculate(&[1.0]), (0.0, 0.0));
+        assert_pair_approx_eq(signal.calculate(&[1.0, 0.0]), (-1.0, -1.0));
+        assert_pair_approx_eq(
+            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
+            (8.0, 4.0),
+        );
+        assert_pair_approx_eq(
+            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
+            (1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn test_MinPrice_calculate() {
+        let signal = MinPrice {};
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(signal.calculate(&[1.0]), Some(1.0));
+        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(0.0));
+        assert_eq!(
+            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
+            Some(1.0)
+        );
+        assert_eq!(
+            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn test_MaxPrice_calculate() {
+        let signal = MaxPrice {};
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(signal.calculate(&[1.0]), Some(1.0));
+        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(1.0));
+        assert_eq!(
+            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
+            Some(10.0)
+        );
+        assert_eq!(
+            signal.calculate(&[0.0, 3.0, 5.0, 6.0, 1.0, 2.0, 1.0]),
+            Some(6.0)
+        );
+    }
+
+    #[test]
+    fn test_ArgMaxPrice_calculate() {
+        let signal = ArgMaxPrice {};
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(signal.calculate(&[1.0]), Some(0));
+        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(0));
+        assert_eq!(
+            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
+            Some(6)
+        );
+        // ties resolve to the first occurrence
+        assert_eq!(signal.calculate(&[1.0, 6.0, 5.0, 6.0, 1.0]), Some(1));
+    }
+
+    #[test]
+    fn test_ArgMinPrice_calculate() {
+        let signal = ArgMinPrice {};
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(signal.calculate(&[1.0]), Some(0));
+        assert_eq!(signal.calculate(&[1.0, 0.0]), Some(1));
+        assert_eq!(
+            signal.calculate(&[2.0, 3.0, 5.0, 6.0, 1.0, 2.0, 10.0]),
+            Some(4)
+        );
+        // ties resolve to the first occurrence
+        assert_eq!(signal.calculate(&[3.0, 1.0, 5.0, 1.0, 2.0]), Some(1));
+    }
+
+    #[test]
+    fn test_WindowedSMA_calculate() {
+        let series = vec![2.0, 4.5, 5.3, 6.5, 4.7];
+
+        let signal = WindowedSMA { window_size: 3 };
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[3.933_333_333_333_333, 5.433_333_333_333_333, 5.5],
+        );
+
+        let signal = WindowedSMA { window_size: 5 };
+        assert_series_approx_eq(signal.calculate(&series), &[4.6]);
+
+        let signal = WindowedSMA { window_size: 10 };
+        assert_eq!(signal.calculate(&series), Some(vec![]));
+    }
+
+    #[test]
+    fn test_TrailingReturn_calculate() {
+        let ramp = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+        let signal = TrailingReturn { window: 2 };
+        let returns = signal.calculate(&ramp).unwrap();
+        assert_eq!(returns.len(), ramp.len() - 2);
+        assert_all_approx_eq(&returns, &[2.0, 1.0, 2.0 / 3.0, 0.5]);
+
+        let signal = TrailingReturn { window: 5 };
+        assert_series_approx_eq(signal.calculate(&ramp), &[5.0]);
+
+        // a zero base price doesn't divide by zero
+        let signal = TrailingReturn { window: 1 };
+        assert_series_approx_eq(signal.calculate(&[0.0, 2.0, 3.0]), &[2.0, 0.5]);
+
+        assert_eq!(TrailingReturn { window: 6 }.calculate(&ramp), None);
+        assert_eq!(TrailingReturn { window: 0 }.calculate(&ramp), None);
+        assert_eq!(TrailingReturn { window: 1 }.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_SmaDeviation_calculate() {
+        let signal = SmaDeviation { window_size: 3 };
+        // above: sma of the last three is 4.0
+        assert_some_approx_eq(signal.calculate(&[1.0, 3.0, 3.0, 3.0, 6.0]), 0.5);
+        // below
+        assert_some_approx_eq(signal.calculate(&[1.0, 5.0, 5.0, 2.0]), -0.5);
+        // on the average
+        assert_some_approx_eq(signal.calculate(&[9.0, 2.0, 2.0, 2.0]), 0.0);
+
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(signal.calculate(&[1.0, -1.0, 0.0]), None);
+        assert_eq!(SmaDeviation { window_size: 1 }.calculate(&[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_NewExtreme_calculate() {
+        let signal = NewExtreme { lookback: 3 };
+        assert_eq!(
+            signal.calculate(&[1.0, 5.0, 3.0, 4.0, 5.5]),
+            Some(Extreme::NewHigh)
+        );
+        assert_eq!(
+            signal.calculate(&[1.0, 5.0, 3.0, 4.0, 2.0]),
+            Some(Extreme::NewLow)
+        );
+        assert_eq!(
+            signal.calculate(&[1.0, 5.0, 3.0, 4.0, 3.5]),
+            Some(Extreme::Neither)
+        );
+        // only the lookback counts: the 10.0 is too far back
+        assert_eq!(
+            signal.calculate(&[10.0, 5.0, 3.0, 4.0, 6.0]),
+            Some(Extreme::NewHigh)
+        );
+        // matching the prior high is not a breakout
+        assert_eq!(
+            signal.calculate(&[1.0, 5.0, 3.0, 4.0, 5.0]),
+            Some(Extreme::Neither)
+        );
+
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]), None);
+        assert_eq!(NewExtreme { lookback: 0 }.calculate(&[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_EwmaVolatility_calculate() {
+        // returns are 0.1, -0.1, 0.0
+        let series = [100.0, 110.0, 99.0, 99.0];
+
+        let signal = EwmaVolatility { lambda: 0.5 };
+        assert_series_approx_eq(signal.calculate(&series), &[0.1, 0.1, 0.005_f64.sqrt()]);
+
+        let signal = EwmaVolatility { lambda: 0.94 };
+        let var_2: f64 = 0.94 * 0.01 + 0.06 * 0.01;
+        let var_3: f64 = 0.94 * var_2;
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[0.1, var_2.sqrt(), var_3.sqrt()],
+        );
+
+        assert_eq!(signal.calculate(&[100.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_EwmaVolatility_lambda_range() {
+        let series = [100.0, 110.0, 99.0];
+        for lambda in [0.0, 1.0, -0.5, 1.5, f64::NAN] {
+            assert_eq!(EwmaVolatility { lambda }.calculate(&series), None);
+        }
+    }
+
+    #[test]
+    fn test_UlcerIndex_calculate() {
+        let signal = UlcerIndex;
+        assert_eq!(signal.calculate(&[]), None);
+        assert_some_approx_eq(signal.calculate(&[1.0, 2.0, 3.0, 10.0]), 0.0);
+        assert_some_approx_eq(signal.calculate(&[5.0]), 0.0);
+
+        // drawdowns of 0%, -50%, -25%, 0%
+        let expected = ((2_500.0 + 625.0) / 4.0_f64).sqrt();
+        assert_some_approx_eq(signal.calculate(&[100.0, 50.0, 75.0, 100.0]), expected);
+        // the same depth lasting longer is worse
+        let longer = signal.calculate(&[100.0, 50.0, 50.0, 75.0, 100.0]).unwrap();
+        assert!(longer > expected);
+    }
+
+    #[test]
+    fn test_PercentDaysAboveSma_calculate() {
+        let signal = PercentDaysAboveSma { window_size: 3 };
+        // averages 2, 3, 4, 5 are all below their closes
+        assert_some_approx_eq(signal.calculate(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), 100.0);
+        // choppy: averages alternate between 1.67 and 2.33, so every other close is above
+        assert_some_approx_eq(signal.calculate(&[1.0, 3.0, 1.0, 3.0, 1.0, 3.0]), 50.0);
+        // a falling close equal to its average is not above it
+        assert_some_approx_eq(signal.calculate(&[2.0, 2.0, 2.0, 1.0]), 0.0);
+
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+        assert_eq!(
+            PercentDaysAboveSma { window_size: 1 }.calculate(&[1.0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_CurrentDrawdown_calculate() {
+        let signal = CurrentDrawdown;
+        assert_eq!(signal.calculate(&[]), None);
+        assert_some_approx_eq(signal.calculate(&[5.0, 3.0, 8.0]), 0.0);
+        assert_some_approx_eq(signal.calculate(&[5.0]), 0.0);
+        assert_some_approx_eq(signal.calculate(&[50.0, 200.0, 120.0, 150.0]), -0.25);
+        assert_some_approx_eq(signal.calculate(&[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_MaxDrawdown_calculate() {
+        assert_eq!(MaxDrawdown.calculate(&[]), None);
+        assert_some_approx_eq(MaxDrawdown.calculate(&[1.0, 2.0, 3.0]), 0.0);
+        // the deeper drawdown from 200 to 120 wins over the one from 100 to 80
+        assert_some_approx_eq(
+            MaxDrawdown.calculate(&[100.0, 80.0, 200.0, 120.0, 190.0]),
+            -0.4,
+        );
+    }
+
+    #[test]
+    fn test_AnnualizedReturn_calculate() {
+        // +21% over two years is +10% a year
+        let signal = AnnualizedReturn {
+            periods_per_year: 1.0,
+        };
+        assert_some_approx_eq(signal.calculate(&[100.0, 80.0, 121.0]), 0.1);
+        // +21% over half a year
+        let signal = AnnualizedReturn {
+            periods_per_year: 4.0,
+        };
+        assert_some_approx_eq(
+            signal.calculate(&[100.0, 110.0, 121.0]),
+            1.21_f64.powi(2) - 1.0,
+        );
+
+        assert_eq!(signal.calculate(&[100.0]), None);
+        assert_eq!(signal.calculate(&[0.0, 1.0]), None);
+        let signal = AnnualizedReturn {
+            periods_per_year: 0.0,
+        };
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_CalmarRatio_calculate() {
+        let signal = CalmarRatio {
+            periods_per_year: 1.0,
+        };
+        // 10% a year with a drawdown of 20%
+        assert_some_approx_eq(signal.calculate(&[100.0, 80.0, 121.0]), 0.5);
+        // no drawdown
+        assert_eq!(signal.calculate(&[100.0, 110.0, 121.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_RollingSharpe_calculate() {
+        // steady returns of 100% that turn erratic: 1, 1, 1, -0.5, 1, -0.75
+        let series = [1.0, 2.0, 4.0, 8.0, 4.0, 8.0, 2.0];
+        let signal = RollingSharpe {
+            window: 3,
+            risk_free_daily: 0.0,
+        };
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[
+                0.0,
+                0.577_350_269_189_625_8,
+                0.577_350_269_189_625_8,
+                -0.088_045_090_632_562_37,
+            ],
+        );
+
+        let signal = RollingSharpe {
+            window: 3,
+            risk_free_daily: 0.1,
+        };
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[
+                0.0,
+                0.461_880_215_351_700_65,
+                0.461_880_215_351_700_65,
+                -0.193_699_199_391_637_24,
+            ],
+        );
+
+        assert_eq!(signal.calculate(&series[..3]), None);
+        assert_eq!(signal.calculate(&[]), None);
+        let signal = RollingSharpe {
+            window: 1,
+            risk_free_daily: 0.0,
+        };
+        assert_eq!(signal.calculate(&series), None);
+    }
+
+    #[test]
+    fn test_BollingerBands_calculate() {
+        let signal = BollingerBands {
+            window_size: 2,
+            num_std: 2.0,
+        };
+        let bands = signal.calculate(&[1.0, 3.0, 3.0]).unwrap();
+        assert_eq!(bands.len(), 2);
+        assert_pair_approx_eq(Some((bands[0].0, bands[0].2)), (0.0, 4.0));
+        assert_approx_eq(bands[0].1, 2.0);
+        assert_pair_approx_eq(Some((bands[1].0, bands[1].2)), (3.0, 3.0));
+
+        assert_eq!(signal.calculate(&[1.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_BollingerTouches_calculate() {
+        // jumps to 13 break out above the band, the drop to 7 below it
+        let series = [10.0, 10.0, 10.0, 13.0, 10.0, 10.0, 7.0, 10.0, 10.0, 13.0];
+        let signal = BollingerTouches {
+            window_size: 3,
+            num_std: 1.0,
+        };
+        assert_eq!(signal.calculate(&series), Some((2, 1)));
+
+        // too wide to ever be touched by a single jump
+        let signal = BollingerTouches {
+            window_size: 3,
+            num_std: 2.0,
+        };
+        assert_eq!(signal.calculate(&series), Some((0, 0)));
+
+        assert_eq!(signal.calculate(&series[..2]), None);
+    }
+
+    #[test]
+    fn test_log_returns() {
+        assert!(log_returns(&[1.0]).is_empty());
+        assert_all_approx_eq(
+            &log_returns(&[1.0, std::f64::consts::E, 1.0, 0.0, 2.0]),
+            &[1.0, -1.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_RealizedVolatility_calculate() {
+        let signal = RealizedVolatility {
+            periods_per_year: 252.0,
+        };
+        let series = [100.0, 110.0, 99.0];
+        let variance = 1.1_f64.ln().powi(2) + 0.9_f64.ln().powi(2);
+        assert_some_approx_eq(RealizedVolatility::realized_variance(&series), variance);
+        assert_some_approx_eq(signal.calculate(&series), (variance * 252.0).sqrt());
+
+        let flat = [5.0, 5.0, 5.0];
+        assert_some_approx_eq(RealizedVolatility::realized_variance(&flat), 0.0);
+        assert_some_approx_eq(signal.calculate(&flat), 0.0);
+
+        assert_eq!(RealizedVolatility::realized_variance(&[100.0]), None);
+        assert_eq!(signal.calculate(&[100.0]), None);
+        assert_eq!(signal.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_mean() {
+        assert_eq!(mean(&[]), None);
+        assert_some_approx_eq(mean(&[1.0, 2.0, 6.0]), 3.0);
+    }
+
+    #[test]
+    fn test_RangePercent_calculate() {
+        // a range of 20 around a mean of 100
+        assert_some_approx_eq(RangePercent.calculate(&[90.0, 100.0, 110.0]), 20.0);
+        assert_some_approx_eq(RangePercent.calculate(&[8.0, 12.0, 10.0, 10.0]), 40.0);
+        assert_some_approx_eq(RangePercent.calculate(&[5.0, 5.0, 5.0]), 0.0);
+
+        assert_eq!(RangePercent.calculate(&[-1.0, 1.0]), None);
+        assert_eq!(RangePercent.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_RelativeStrengthIndex_calculate() {
+        let signal = RelativeStrengthIndex { period: 3 };
+        let series = [10.0, 11.0, 12.0, 13.0, 12.0, 11.0, 12.0];
+        // seeded with gains 1, 1, 1 then losses of 1, 1 and a gain of 1
+        let (gain_1, loss_1) = (2.0 / 3.0, 1.0 / 3.0);
+        let (gain_2, loss_2) = (gain_1 * 2.0 / 3.0, (loss_1 * 2.0 + 1.0) / 3.0);
+        let (gain_3, loss_3) = ((gain_2 * 2.0 + 1.0) / 3.0, loss_2 * 2.0 / 3.0);
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[
+                100.0,
+                100.0 - 100.0 / (1.0 + gain_1 / loss_1),
+                100.0 - 100.0 / (1.0 + gain_2 / loss_2),
+                100.0 - 100.0 / (1.0 + gain_3 / loss_3),
+            ],
+        );
+
+        assert_series_approx_eq(signal.calculate(&[5.0, 5.0, 5.0, 5.0]), &[50.0]);
+        assert_eq!(signal.calculate(&series[..3]), None);
+        assert_eq!(RelativeStrengthIndex { period: 0 }.calculate(&series), None);
+    }
+
+    #[test]
+    fn test_RsiDivergence_calculate() {
+        let signal = RsiDivergence {
+            rsi_period: 3,
+            lookback: 6,
+        };
+        // a rally, a pullback and a weak new high
+        let bearish = [
+            10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 14.0, 13.0, 12.0, 13.0, 15.5,
+        ];
+        assert_eq!(signal.calculate(&bearish), Some(Divergence::Bearish));
+        // the mirror image: a selloff, a bounce and a weak new low
+        let bullish = [
+            20.0, 19.0, 18.0, 17.0, 16.0, 15.0, 16.0, 17.0, 18.0, 17.0, 14.5,
+        ];
+        assert_eq!(signal.calculate(&bullish), Some(Divergence::Bullish));
+        // a new high confirmed by the RSI
+        let trend: Vec<f64> = (10..=20).map(f64::from).collect();
+        assert_eq!(signal.calculate(&trend), Some(Divergence::Neither));
+
+        assert_eq!(signal.calculate(&bearish[..9]), None);
+        let signal = RsiDivergence {
+            rsi_period: 3,
+            lookback: 0,
+        };
+        assert_eq!(signal.calculate(&bearish), None);
+    }
+
+    fn prices(returns: &[f64]) -> Vec<f64> {
+        let mut prices = vec![100.0];
+        for r in returns {
+            prices.push(prices.last().unwrap() * (1.0 + r));
+        }
+        prices
+    }
+
+    #[test]
+    fn test_CaptureRatios_calculate() {
+        let benchmark = prices(&[0.1, -0.1, 0.05, -0.05, 0.0]);
+        // 1.2 times the benchmark's gains, 0.8 times its losses
+        let asset = prices(&[0.12, -0.08, 0.06, -0.04, 0.01]);
+        assert_some_approx_eq(upside_capture(&asset, &benchmark), 1.2);
+        assert_some_approx_eq(downside_capture(&asset, &benchmark), 0.8);
+        let signal = CaptureRatios {
+            benchmark: benchmark.clone(),
+        };
+        assert_pair_approx_eq(signal.calculate(&asset), (1.2, 0.8));
+
+        // a longer history of the asset is aligned at the end
+        let mut longer = vec![1.0, 50.0];
+        longer.extend(&asset);
+        assert_pair_approx_eq(signal.calculate(&longer), (1.2, 0.8));
+    }
+
+    #[test]
+    fn test_CaptureRatios_one_sided() {
+        let rising = prices(&[0.1, 0.05]);
+        let asset = prices(&[0.2, 0.1]);
+        assert_some_approx_eq(upside_capture(&asset, &rising), 2.0);
+        assert_eq!(downside_capture(&asset, &rising), None);
+        let signal = CaptureRatios { benchmark: rising };
+        assert_eq!(signal.calculate(&asset), None);
+        assert_eq!(upside_capture(&asset, &[]), None);
+    }
+
+    #[test]
+    fn test_Aroon_calculate() {
+        let signal = Aroon { period: 4 };
+        // the latest close is the high, the low was 3 bars ago
+        assert_pair_approx_eq(
+            signal.calculate(&[9.0, 5.0, 2.0, 3.0, 4.0, 6.0]),
+            (100.0, 25.0),
+        );
+        // the high was `period` bars ago, the latest close is the low
+        assert_pair_approx_eq(signal.calculate(&[8.0, 7.0, 6.0, 5.0, 4.0]), (0.0, 100.0));
+        // ties count as the most recent extreme
+        assert_pair_approx_eq(signal.calculate(&[5.0, 1.0, 5.0, 1.0, 3.0]), (50.0, 75.0));
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0, 4.0]), None);
+        assert_eq!(Aroon { period: 0 }.calculate(&[1.0]), None);
+    }
+
+    #[test]
+    fn test_ema() {
+        // seeded with (1 + 2) / 2, then smoothed by 2/3
+        assert_series_approx_eq(
+            ema(&[1.0, 2.0, -1.0, 2.0], 2),
+            &[1.5, -1.0 / 6.0, 23.0 / 18.0],
+        );
+        assert_series_approx_eq(ema(&[4.0], 1), &[4.0]);
+        assert_eq!(ema(&[1.0], 2), None);
+        assert_eq!(ema(&[1.0], 0), None);
+    }
+
+    #[test]
+    fn test_seeded() {
+        let seed = [1.0, 2.0, 3.0, 4.0];
+        let fetched = [5.0, 4.0, 6.0];
+        let seeded = seeded(&seed, &fetched, |s| ema(s, 3)).unwrap();
+        let full = ema(&[&seed[..], &fetched].concat(), 3).unwrap();
+        // warm from the first fetched bar on
+        assert_eq!(seeded.len(), fetched.len());
+        assert_all_approx_eq(&seeded, &full[full.len() - 3..]);
+        // without a seed it's the plain signal
+        assert_series_approx_eq(super::seeded(&[], &fetched, |s| ema(s, 3)), &[5.0]);
+    }
+
+    #[test]
+    fn test_TrueStrengthIndex_calculate() {
+        let signal = TrueStrengthIndex { long: 2, short: 2 };
+        // momentum [1, 2, -1, 2] smooths to [2/3, 29/27] and its absolute
+        // values [1, 2, 1, 2] to [4/3, 43/27]
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 4.0, 3.0, 5.0]),
+            &[50.0, 2900.0 / 43.0],
+        );
+        assert_series_approx_eq(signal.calculate(&[3.0, 3.0, 3.0, 3.0]), &[0.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]), None);
+        assert_eq!(TrueStrengthIndex::default().calculate(&[1.0; 37]), None);
+        assert_eq!(
+            TrueStrengthIndex::default()
+                .calculate(&[1.0; 38])
+                .map(|tsi| tsi.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_WinRate_calculate() {
+        assert_some_approx_eq(WinRate.calculate(&[1.0, 2.0, 3.0, 4.0]), 100.0);
+        assert_some_approx_eq(WinRate.calculate(&[4.0, 3.0, 2.0]), 0.0);
+        // up, flat, down, up
+        assert_some_approx_eq(WinRate.calculate(&[1.0, 2.0, 2.0, 1.5, 3.0]), 50.0);
+        assert_eq!(WinRate.calculate(&[1.0]), None);
+        assert_eq!(WinRate.calculate(&[]), None);
+    }
+
+    #[test]
+    fn test_MaSpread_calculate() {
+        let signal = MaSpread { fast: 2, slow: 4 };
+        let rising = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        // each fast average is 1 above the slow one ending at the same close
+        assert_series_approx_eq(signal.calculate(&rising), &[1.0, 1.0, 1.0]);
+        let falling: Vec<_> = rising.iter().rev().copied().collect();
+        assert_series_approx_eq(signal.calculate(&falling), &[-1.0, -1.0, -1.0]);
+        // the windows may be given either way round
+        assert_series_approx_eq(MaSpread { fast: 4, slow: 2 }.calculate(&rising), &[-1.0; 3]);
+        assert_eq!(signal.calculate(&rising[..3]), None);
+        assert_eq!(MaSpread { fast: 1, slow: 4 }.calculate(&rising), None);
+    }
+
+    #[test]
+    fn test_DetrendedPriceOscillator_calculate() {
+        // period 4 displaces by 3: the close 3 bars back less the average
+        let signal = DetrendedPriceOscillator { period: 4 };
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            &[-1.5, -1.5, -1.5],
+        );
+        // the average of a whole cycle is its center, leaving the cycle
+        let signal = DetrendedPriceOscillator { period: 20 };
+        let series: Vec<f64> = (0..200_u32)
+            .map(|i| 100.0 + (f64::from(i) * std::f64::consts::TAU / 20.0).sin())
+            .collect();
+        let dpo = signal.calculate(&series).unwrap();
+        assert_eq!(dpo.len(), 181);
+        assert!(mean(&dpo).unwrap().abs() < 0.01);
+        assert!(dpo.iter().all(|v| v.abs() <= 1.0 + 1e-9));
+        assert!(dpo.iter().any(|v| *v > 0.9) && dpo.iter().any(|v| *v < -0.9));
+
+        assert_eq!(signal.calculate(&series[..19]), None);
+        assert_eq!(
+            DetrendedPriceOscillator { period: 2 }.calculate(&[1.0, 2.0]),
+            None
+        );
+        assert_series_approx_eq(
+            DetrendedPriceOscillator { period: 2 }.calculate(&[1.0, 2.0, 3.0]),
+            &[-1.5],
+        );
+    }
+
+    #[test]
+    fn test_KaufmanAma_calculate() {
+        // smoothing constants 2/3 and 2/5
+        let signal = KaufmanAma {
+            period: 2,
+            fast: 2,
+            slow: 4,
+        };
+        // a clean trend (er = 1) moves by (2/3)^2 of the distance, the noisy
+        // back and forth (er = 0) only by (2/5)^2
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 2.0, 3.0]),
+            &[2.0, 22.0 / 9.0, 534.0 / 225.0, 13_914.0 / 5_625.0],
+        );
+        assert_series_approx_eq(signal.calculate(&[5.0, 5.0, 5.0]), &[5.0, 5.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        assert_eq!(
+            KaufmanAma {
+                period: 2,
+                fast: 0,
+                slow: 4
+            }
+            .calculate(&[1.0, 2.0, 3.0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_SmaSlope_calculate() {
+        let signal = SmaSlope {
+            window_size: 3,
+            lookback: 2,
+        };
+        // averages 2, 4, 6, 8
+        assert_some_approx_eq(signal.calculate(&[1.0, 2.0, 3.0, 7.0, 8.0, 9.0]), 2.0);
+        // averages 5, 4, 3
+        assert_some_approx_eq(signal.calculate(&[6.0, 5.0, 4.0, 3.0, 2.0]), -1.0);
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0, 4.0]), None);
+        assert_eq!(
+            SmaSlope {
+                window_size: 3,
+                lookback: 0
+            }
+            .calculate(&[1.0; 10]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_FisherTransform_calculate() {
+        let signal = FisherTransform { period: 3 };
+        // every close is the high of its window, x saturates at the clamp
+        let rising: Vec<f64> = (1..=50_u32).map(f64::from).collect();
+        let fish = signal.calculate(&rising).unwrap();
+        assert_eq!(fish.len(), 48);
+        assert!(fish.iter().all(|f| f.is_finite() && *f > 0.0));
+        // 0.33, then 0.33 + 0.67 * 0.33
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 4.0]),
+            &[
+                0.5 * (1.33_f64 / 0.67).ln(),
+                0.5 * (1.5511_f64 / 0.4489).ln() + 0.25 * (1.33_f64 / 0.67).ln(),
+            ],
+        );
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        let fish = signal.calculate(&falling).unwrap();
+        assert!(fish.iter().all(|f| f.is_finite() && *f < 0.0));
+        assert_series_approx_eq(signal.calculate(&[2.0; 4]), &[0.0, 0.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        assert_eq!(FisherTransform { period: 0 }.calculate(&[1.0]), None);
+    }
+
+    #[test]
+    fn test_PercentagePriceOscillator_calculate() {
+        let signal = PercentagePriceOscillator {
+            fast: 2,
+            slow: 3,
+            signal: 2,
+        };
+        // the fast EMA [2.5, 3.5, 4.5, 5.5] is 0.5 above the slow one
+        // [2, 3, 4, 5], the signal line is seeded with (25 + 50/3) / 2
+        let lines = signal.calculate(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        let ppo: Vec<_> = lines.iter().map(|l| l.0).collect();
+        let signal_line: Vec<_> = lines.iter().map(|l| l.1).collect();
+        let histogram: Vec<_> = lines.iter().map(|l| l.2).collect();
+        assert_all_approx_eq(&ppo, &[50.0 / 3.0, 12.5, 10.0]);
+        assert_all_approx_eq(&signal_line, &[125.0 / 6.0, 275.0 / 18.0, 635.0 / 54.0]);
+        assert_all_approx_eq(&histogram, &[-25.0 / 6.0, -25.0 / 9.0, -95.0 / 54.0]);
+
+        // scale independent
+        let scaled = signal.calculate(&[100.0, 200.0, 300.0, 400.0, 500.0, 600.0]);
+        assert_eq!(scaled.map(|l| l.len()), Some(3));
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]), None);
+        assert_eq!(signal.calculate(&[0.0, 0.0, 0.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn test_RocPercentile_calculate() {
+        let signal = RocPercentile {
+            roc_lag: 1,
+            lookback: 4,
+        };
+        // returns 10%, 0%, -10%, 50%: the latest is the highest
+        assert_some_approx_eq(signal.calculate(&[1.0, 1.1, 1.1, 0.99, 1.485]), 100.0);
+        // returns 10%, 50%, 0%, -50%: the latest is the lowest
+        assert_some_approx_eq(signal.calculate(&[2.0, 2.2, 3.3, 3.3, 1.65]), 0.0);
+        // only the last `lookback` returns count, the 100% is too old
+        assert_some_approx_eq(
+            signal.calculate(&[1.0, 2.0, 2.2, 2.2, 1.98, 2.079]),
+            200.0 / 3.0,
+        );
+        assert_eq!(signal.calculate(&[1.0, 1.1, 1.1, 0.99]), None);
+        assert_eq!(
+            RocPercentile {
+                roc_lag: 1,
+                lookback: 1
+            }
+            .calculate(&[1.0, 2.0, 3.0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_SchaffTrendCycle_calculate() {
+        let signal = SchaffTrendCycle {
+            macd_fast: 3,
+            macd_slow: 6,
+            cycle: 4,
+        };
+        let varied: Vec<f64> = (0..60_u32)
+            .map(|i| 100.0 + 10.0 * (f64::from(i) / 4.0).sin() + f64::from(i % 7))
+            .collect();
+        let stc = signal.calculate(&varied).unwrap();
+        // 55 MACD values less two warm-ups of 3
+        assert_eq!(stc.len(), 49);
+        assert!(stc.iter().all(|v| (0.0..=100.0).contains(v)));
+        assert!(stc.iter().any(|v| *v > 75.0) && stc.iter().any(|v| *v < 25.0));
+
+        // a rally after a decline drives the cycle to the top
+        let rally: Vec<f64> = (0..30_u32)
+            .map(|i| f64::from(if i < 15 { 50 - i } else { 5 * i - 40 }))
+            .collect();
+        let stc = signal.calculate(&rally).unwrap();
+        assert!(stc.iter().all(|v| (0.0..=100.0).contains(v)));
+        assert!(*stc.last().unwrap() > 90.0);
+        // a flat MACD never leaves the middle
+        assert_series_approx_eq(signal.calculate(&[5.0; 12]), &[50.0; 1]);
+        assert_eq!(signal.calculate(&[1.0; 11]), None);
+        assert_eq!(
+            SchaffTrendCycle {
+                macd_fast: 3,
+                macd_slow: 6,
+                cycle: 0
+            }
+            .calculate(&varied),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wma() {
+        assert_series_approx_eq(wma(&[1.0, 2.0, 3.0, 6.0], 3), &[14.0 / 6.0, 26.0 / 6.0]);
+        assert_series_approx_eq(wma(&[4.0, 5.0], 1), &[4.0, 5.0]);
+        assert_eq!(wma(&[1.0, 2.0], 3), None);
+        assert_eq!(wma(&[1.0, 2.0], 0), None);
+    }
+
+    #[test]
+    fn test_CoppockCurve_calculate() {
+        let signal = CoppockCurve {
+            roc1: 1,
+            roc2: 2,
+            wma: 2,
+        };
+        // ROCs 100, 50, 100/3, 25 and 200, 100, 200/3 add up to
+        // 250, 400/3, 275/3
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 4.0, 5.0]),
+            &[1550.0 / 9.0, 950.0 / 9.0],
+        );
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]), None);
+
+        // four years of monthly closes: two falling, then two rising
+        let monthly: Vec<f64> = (0..48_u32)
+            .map(|month| {
+                let month = f64::from(month);
+                if month < 24.0 {
+                    100.0 - 2.0 * month
+                } else {
+                    52.0 + 3.0 * (month - 24.0)
+                }
+            })
+            .collect();
+        let classic = CoppockCurve {
+            roc1: 14,
+            roc2: 11,
+            wma: 10,
+        };
+        let curve = classic.calculate(&monthly).unwrap();
+        assert_eq!(curve.len(), 48 - 14 - 10 + 1);
+        // negative through the decline, crossing above zero once in the rally
+        assert!(curve[0] < 0.0);
+        assert!(*curve.last().unwrap() > 0.0);
+        let crossings = curve
+            .windows(2)
+            .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+            .count();
+        assert_eq!(crossings, 1);
+        assert_eq!(
+            curve
+                .windows(2)
+                .filter(|w| w[0] >= 0.0 && w[1] < 0.0)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_ChandeMomentumOscillator_calculate() {
+        let signal = ChandeMomentumOscillator { period: 3 };
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 4.0, 5.0, 8.0]),
+            &[100.0, 100.0],
+        );
+        assert_series_approx_eq(signal.calculate(&[5.0, 4.0, 2.0, 1.0]), &[-100.0]);
+        // gains 1 + 2 and a loss of 1, then gains 2 and a loss of 1 and 3
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 1.0, 3.0, 0.0]),
+            &[50.0, -100.0 / 3.0],
+        );
+        assert_series_approx_eq(signal.calculate(&[2.0; 4]), &[0.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0]), None);
+        assert_eq!(
+            ChandeMomentumOscillator { period: 0 }.calculate(&[1.0, 2.0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_InstantaneousTrendline_calculate() {
+        let signal = InstantaneousTrendline { alpha: 0.5 };
+        // a straight line is followed exactly
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 4.0]),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // 0.4375 * 1 + 0.125 * 3 - 0.3125 * 2 + 3 - 0.25 * 2
+        assert_series_approx_eq(
+            signal.calculate(&[1.0, 2.0, 3.0, 1.0]),
+            &[1.0, 2.0, 3.0, 2.6875],
+        );
+        assert_series_approx_eq(signal.calculate(&[5.0; 4]), &[5.0; 4]);
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        for alpha in [0.0, 1.0, -0.5, 1.5] {
+            assert_eq!(
+                InstantaneousTrendline { alpha }.calculate(&[1.0, 2.0, 3.0]),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_ConnorsRsi_calculate() {
+        let signal = ConnorsRsi {
+            rsi_period: 2,
+            streak_rsi_period: 2,
+            roc_lookback: 2,
+        };
+        let series = [1.0, 2.0, 3.0, 2.0, 4.0];
+        // price RSI 50 and 83.33, streaks 0, 1, 2, -1, 1 with RSI 25 and
+        // 62.5, returns 1, 0.5, -1/3, 1 ranking 0 and 100
+        assert_series_approx_eq(
+            signal.calculate(&series),
+            &[25.0, (250.0 / 3.0 + 62.5 + 100.0) / 3.0],
+        );
+        assert_eq!(signal.calculate(&series[..3]), None);
+        assert_eq!(
+            ConnorsRsi {
+                roc_lookback: 0,
+                ..signal
+            }
+            .calculate(&series),
+            None
+        );
+        assert_eq!(
+            ConnorsRsi {
+                rsi_period: 0,
+                ..signal
+            }
+            .calculate(&series),
+            None
+        );
+    }
+
+    #[test]
+    fn test_TrendIntensityIndex_calculate() {
+        let signal = TrendIntensityIndex { period: 3 };
+        let rising: Vec<f64> = (1..=10).map(f64::from).collect();
+        assert_series_approx_eq(signal.calculate(&rising), &[100.0; 6]);
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        assert_series_approx_eq(signal.calculate(&falling), &[0.0; 6]);
+        assert_series_approx_eq(signal.calculate(&[5.0; 6]), &[50.0; 2]);
+        // deviations 1, -1/3, 5/3 from the averages 2, 7/3, 10/3
+        assert_series_approx_eq(signal.calculate(&[1.0, 2.0, 3.0, 2.0, 5.0]), &[800.0 / 9.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0, 3.0, 4.0]), None);
+        assert_eq!(TrendIntensityIndex { period: 1 }.calculate(&rising), None);
+    }
+
+    #[test]
+    fn test_DisparityIndex_calculate() {
+        let signal = DisparityIndex { period: 3 };
+        // averages 2, 3, 4, 11/3
+        let disparity = signal.calculate(&[1.0, 2.0, 3.0, 4.0, 5.0, 2.0]);
+        assert_eq!(disparity.as_ref().map(Vec::len), Some(4));
+        assert_series_approx_eq(disparity, &[50.0, 100.0 / 3.0, 25.0, -500.0 / 11.0]);
+        assert_series_approx_eq(signal.calculate(&[2.0; 3]), &[0.0]);
+        assert_eq!(signal.calculate(&[1.0, 2.0]), None);
+        assert_eq!(signal.calculate(&[-1.0, 0.0, 1.0, 2.0]), None);
+        assert_eq!(DisparityIndex { period: 1 }.calculate(&[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_StochasticRsi_calculate() {
+        let signal = StochasticRsi {
+            rsi_period: 3,
+            stoch_period: 4,
+            k_smooth: 3,
+            d_smooth: 3,
+        };
+        let series: Vec<f64> = (0..40)
+            .map(|i| 100.0 + 10.0 * (f64::from(i) * 0.7).sin() + f64::from(i % 3))
+            .collect();
+        let values = signal.calculate(&series).unwrap();
+        // 37 RSI values, 34 stochastics, 32 %K and 30 %D
+        assert_eq!(values.len(), 30);
+        assert!(values
+            .iter()
+            .all(|(k, d)| (0.0..=100.0).contains(k) && (0.0..=100.0).contains(d)));
+        assert!(values.iter().any(|(k, _)| *k < 20.0));
+        assert!(values.iter().any(|(k, _)| *k > 80.0));
+
+        // the RSI of a steady rise stays at 100 without any range
+        let rising: Vec<f64> = (1..=12).map(f64::from).collect();
+        let flat = signal.calculate(&rising).unwrap();
+        assert_eq!(flat.len(), 2);
+        for (k, d) in flat {
+            assert_pair_approx_eq(Some((k, d)), (50.0, 50.0));
+        }
+        // unsmoothed, %K is the stochastic itself: the RSIs 100, 50, 100
+        let unsmoothed = StochasticRsi {
+            rsi_period: 1,
+            stoch_period: 2,
+            k_smooth: 1,
+            d_smooth: 1,
+        };
+        assert_eq!(
+            unsmoothed.calculate(&[1.0, 2.0, 2.0, 3.0]),
+            Some(vec![(0.0, 0.0), (100.0, 100.0)])
+        );
+
+        assert_eq!(signal.calculate(&rising[..10]), None);
+        assert_eq!(
+            StochasticRsi {
+                d_smooth: 0,
+                ..signal
+            }
+            .calculate(&series),
+            None
+        );
+    }
+}
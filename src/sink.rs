@@ -0,0 +1,331 @@
+//!
+//! Destinations for the rendered output of each tick.
+//!
+
+use chrono::prelude::*;
+use clap::ArgEnum;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+///
+/// When to switch to a new output file.
+///
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    /// A new file per UTC day, named by inserting the date before the extension
+    Daily,
+}
+
+enum Target {
+    Stdout,
+    File(File),
+    Replace(PathBuf),
+    Gzip(File),
+    Daily {
+        path: PathBuf,
+        date: Option<NaiveDate>,
+        file: Option<File>,
+    },
+}
+
+impl Target {
+    fn write(
+        &mut self,
+        now: DateTime<Utc>,
+        header: Option<&str>,
+        contents: &[u8],
+    ) -> io::Result<()> {
+        match self {
+            Target::Stdout => write_stdout(contents),
+            Target::File(file) => file.write_all(contents),
+            Target::Replace(path) => fs::write(path, contents),
+            Target::Gzip(file) => {
+                // a complete member per tick, so the file is always readable
+                let mut encoder = GzEncoder::new(&mut *file, Compression::default());
+                encoder.write_all(contents)?;
+                encoder.finish()?.flush()
+            }
+            Target::Daily { path, date, file } => {
+                let today = now.date().naive_utc();
+                if *date != Some(today) || file.is_none() {
+                    *file = Some(open_append(&dated_path(path, today), header)?);
+                    *date = Some(today);
+                }
+                file.iter_mut()
+                    .try_for_each(|file| file.write_all(contents))
+            }
+        }
+    }
+}
+
+///
+/// Writes each tick's output to stdout, a file or several of them, starting
+/// each file (and stdout) with the format's header.
+///
+pub struct Sink {
+    targets: Vec<Target>,
+    header: Option<String>,
+}
+
+///
+/// Insert the date before the extension of `path`, e.g. `report.csv` becomes
+/// `report-2024-06-01.csv`.
+///
+pub fn dated_path(path: &Path, date: NaiveDate) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{date}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{date}"),
+    };
+    path.with_file_name(name)
+}
+
+///
+/// Open a file for appending and write the header if the file is new.
+///
+fn open_append(path: &Path, header: Option<&str>) -> io::Result<File> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if let Some(header) = header {
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{header}")?;
+        }
+    }
+    Ok(file)
+}
+
+///
+/// Write all of `contents` at once while holding the stdout lock, so output
+/// from elsewhere can't end up in the middle of a tick.
+///
+fn write_stdout(contents: &[u8]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(contents)?;
+    stdout.flush()
+}
+
+impl Sink {
+    ///
+    /// A sink printing to stdout, starting with the header right away.
+    ///
+    pub fn stdout(header: Option<&str>) -> Self {
+        if let Some(header) = header {
+            // a failing stdout fails the first tick as well
+            let _ = write_stdout(format!("{header}\n").as_bytes());
+        }
+        Sink {
+            targets: vec![Target::Stdout],
+            header: header.map(ToString::to_string),
+        }
+    }
+
+    ///
+    /// A sink appending to the file at `path`, or to one file per day derived
+    /// from it if rotated.
+    ///
+    /// # Errors
+    ///
+    /// If the (unrotated) file can't be opened.
+    ///
+    pub fn file(path: &Path, rotation: Option<Rotation>, header: Option<&str>) -> io::Result<Self> {
+        let target = match rotation {
+            None => Target::File(open_append(path, header)?),
+            Some(Rotation::Daily) => Target::Daily {
+                path: path.to_path_buf(),
+                date: None,
+                file: None,
+            },
+        };
+        Ok(Sink {
+            targets: vec![target],
+            header: header.map(ToString::to_string),
+        })
+    }
+
+    ///
+    /// A sink replacing the file at `path` with every tick, for formats
+    /// where each tick is a complete document.
+    ///
+    pub fn replace(path: &Path) -> Self {
+        Sink {
+            targets: vec![Target::Replace(path.to_path_buf())],
+            header: None,
+        }
+    }
+
+    ///
+    /// A sink appending each tick to the file at `path` as a gzip member of
+    /// its own, so the file can be decompressed up to the last complete tick
+    /// at any time.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be opened.
+    ///
+    pub fn gzip(path: &Path) -> io::Result<Self> {
+        Ok(Sink {
+            targets: vec![Target::Gzip(open_append(path, None)?)],
+            header: None,
+        })
+    }
+
+    ///
+    /// Write each tick to the targets of `other` as well. Both sinks are
+    /// expected to have the same header.
+    ///
+    pub fn tee(mut self, other: Sink) -> Self {
+        self.targets.extend(other.targets);
+        self
+    }
+
+    ///
+    /// Write a tick's output to every target, switching files first if `now`
+    /// is on another day than the previous tick of a daily rotated sink. A
+    /// failing target doesn't keep the others from being written to.
+    ///
+    /// # Errors
+    ///
+    /// The first error of opening or writing to a target.
+    ///
+    pub fn write_tick(&mut self, now: DateTime<Utc>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        let contents = contents.as_ref();
+        let mut result = Ok(());
+        for target in &mut self.targets {
+            let written = target.write(now, self.header.as_deref(), contents);
+            if result.is_ok() {
+                result = written;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use std::{fs, io::Read, process::Command};
+
+    ///
+    /// A fresh, empty directory for the files of a single test.
+    ///
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ffl-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_dated_path() {
+        let date = NaiveDate::from_ymd(2024, 6, 1);
+        assert_eq!(
+            dated_path(Path::new("out/report.csv"), date),
+            PathBuf::from("out/report-2024-06-01.csv")
+        );
+        assert_eq!(
+            dated_path(Path::new("report"), date),
+            PathBuf::from("report-2024-06-01")
+        );
+    }
+
+    #[test]
+    fn test_Sink_file() {
+        let dir = temp_dir("sink-file");
+        let path = dir.join("report.csv");
+        let now = Utc.ymd(2024, 6, 1).and_hms(12, 0, 0);
+        let mut sink = Sink::file(&path, None, Some("a,b")).unwrap();
+        sink.write_tick(now, "1,2\n").unwrap();
+        // reopening an existing file doesn't repeat the header
+        let mut sink = Sink::file(&path, None, Some("a,b")).unwrap();
+        sink.write_tick(now, "3,4\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\n1,2\n3,4\n");
+    }
+
+    #[test]
+    fn test_Sink_daily_rotation() {
+        let dir = temp_dir("sink-daily");
+        let path = dir.join("report.csv");
+        let mut sink = Sink::file(&path, Some(Rotation::Daily), Some("a,b")).unwrap();
+        sink.write_tick(Utc.ymd(2024, 6, 1).and_hms(23, 59, 0), "1,2\n")
+            .unwrap();
+        sink.write_tick(Utc.ymd(2024, 6, 1).and_hms(23, 59, 30), "3,4\n")
+            .unwrap();
+        sink.write_tick(Utc.ymd(2024, 6, 2).and_hms(0, 0, 0), "5,6\n")
+            .unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("report-2024-06-01.csv")).unwrap(),
+            "a,b\n1,2\n3,4\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("report-2024-06-02.csv")).unwrap(),
+            "a,b\n5,6\n"
+        );
+    }
+
+    #[test]
+    fn test_Sink_tee() {
+        let dir = temp_dir("sink-tee");
+        let (path, copy) = (dir.join("report.csv"), dir.join("copy.csv"));
+        let mut sink = Sink::file(&path, None, None)
+            .unwrap()
+            .tee(Sink::file(&copy, None, None).unwrap());
+        let now = Utc.ymd(2024, 6, 1).and_hms(12, 0, 0);
+        sink.write_tick(now, "1,2\n3,4\n").unwrap();
+        sink.write_tick(now, "5,6\n").unwrap();
+
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written, b"1,2\n3,4\n5,6\n");
+        assert_eq!(fs::read(&copy).unwrap(), written);
+    }
+
+    #[test]
+    fn test_Sink_gzip() {
+        let dir = temp_dir("sink-gzip");
+        let path = dir.join("report.ndjson.gz");
+        let now = Utc.ymd(2024, 6, 1).and_hms(12, 0, 0);
+        let first =
+            "{\"symbol\":\"AAPL\",\"price\":150.5}\n{\"symbol\":\"MSFT\",\"price\":330.25}\n";
+        let second = "{\"symbol\":\"AAPL\",\"price\":151.0}\n";
+        let mut sink = Sink::gzip(&path).unwrap();
+        sink.write_tick(now, first).unwrap();
+        // reopening appends another member
+        let mut sink = Sink::gzip(&path).unwrap();
+        sink.write_tick(now, second).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        assert_eq!(written[..2], [0x1f, 0x8b]);
+        let mut lines = String::new();
+        flate2::read::MultiGzDecoder::new(&written[..])
+            .read_to_string(&mut lines)
+            .unwrap();
+        assert_eq!(
+            lines.lines().collect::<Vec<_>>(),
+            [
+                "{\"symbol\":\"AAPL\",\"price\":150.5}",
+                "{\"symbol\":\"MSFT\",\"price\":330.25}",
+                "{\"symbol\":\"AAPL\",\"price\":151.0}",
+            ]
+        );
+        // the gzip tool reads the members as one stream as well
+        let gunzipped = Command::new("gzip").arg("-dc").arg(&path).output().unwrap();
+        assert!(gunzipped.status.success());
+        assert_eq!(String::from_utf8(gunzipped.stdout).unwrap(), lines);
+    }
+
+    #[test]
+    fn test_Sink_replace() {
+        let dir = temp_dir("sink-replace");
+        let path = dir.join("report.html");
+        let mut sink = Sink::replace(&path);
+        let now = Utc.ymd(2024, 6, 1).and_hms(12, 0, 0);
+        sink.write_tick(now, "<html>1</html>\n").unwrap();
+        sink.write_tick(now, "<html>2</html>\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<html>2</html>\n");
+    }
+}
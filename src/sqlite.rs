@@ -0,0 +1,212 @@
+//!
+//! Persisting each tick's reports in an `SQLite` database.
+//!
+
+use chrono::prelude::*;
+use rusqlite::{params, Connection};
+use std::{
+    io::{self, Error},
+    path::Path,
+};
+
+use crate::report::SymbolReport;
+
+///
+/// A row per report and tick. Timestamps are RFC 3339 text, the series are
+/// JSON arrays and values `SQLite` can't store, like NaN, are `NULL`.
+///
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS reports (
+    run_time TEXT NOT NULL,
+    period_start TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    last_price REAL,
+    pct_change REAL,
+    period_min REAL,
+    period_max REAL,
+    sma REAL,
+    last_close_time TEXT NOT NULL,
+    sma_series TEXT,
+    external TEXT
+)";
+
+const INSERT: &str = "INSERT INTO reports (
+    run_time, period_start, symbol, last_price, pct_change, period_min, period_max, sma,
+    last_close_time, sma_series, external
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)";
+
+///
+/// A database the reports of every tick are inserted into.
+///
+#[derive(Debug)]
+pub struct ReportDb {
+    connection: Connection,
+}
+
+impl ReportDb {
+    ///
+    /// Open the database at `path`, creating it and its `reports` table if
+    /// they don't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be opened or the table can't be created.
+    ///
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let connection = Connection::open(path).map_err(Error::other)?;
+        connection.execute_batch(SCHEMA).map_err(Error::other)?;
+        Ok(ReportDb { connection })
+    }
+
+    ///
+    /// Insert the reports of the tick run at `run_time` in a single
+    /// transaction, so a tick is either stored completely or not at all.
+    ///
+    /// # Errors
+    ///
+    /// If any of the reports can't be inserted.
+    ///
+    pub fn insert_tick(
+        &mut self,
+        run_time: DateTime<Utc>,
+        reports: &[SymbolReport],
+    ) -> io::Result<()> {
+        let transaction = self.connection.transaction().map_err(Error::other)?;
+        {
+            let mut insert = transaction.prepare_cached(INSERT).map_err(Error::other)?;
+            for report in reports {
+                insert
+                    .execute(params![
+                        run_time.to_rfc3339(),
+                        report.period_start.to_rfc3339(),
+                        report.symbol,
+                        report.last_price,
+                        report.pct_change,
+                        report.period_min,
+                        report.period_max,
+                        report.sma,
+                        report.last_close_time.to_rfc3339(),
+                        to_json(report.sma_series.as_ref()),
+                        to_json(report.external.as_ref()),
+                    ])
+                    .map_err(Error::other)?;
+            }
+        }
+        transaction.commit().map_err(Error::other)
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: Option<&T>) -> Option<String> {
+    value.map(|value| serde_json::to_string(value).expect("series always serialize to JSON"))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::{output, report::SeriesPoint, sink};
+
+    ///
+    /// The columns of a row of the `reports` table, in order.
+    ///
+    type Record = (
+        String,
+        String,
+        String,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+        Option<f64>,
+        String,
+        Option<String>,
+        Option<String>,
+    );
+
+    #[test]
+    fn test_ReportDb_insert_tick() {
+        let path = sink::tests::temp_dir("sqlite").join("reports.db");
+        let (first, second) = (
+            Utc.ymd(2021, 11, 5).and_hms(16, 0, 0),
+            Utc.ymd(2021, 11, 5).and_hms(16, 0, 30),
+        );
+        ReportDb::open(&path)
+            .unwrap()
+            .insert_tick(first, &output::tests::reports())
+            .unwrap();
+        // reopening keeps the table and its rows
+        let mut db = ReportDb::open(&path).unwrap();
+        let aapl = SymbolReport {
+            pct_change: f64::NAN,
+            sma_series: Some(vec![SeriesPoint {
+                timestamp: Utc.ymd(2021, 11, 5).and_hms(0, 0, 0),
+                value: 148.0,
+            }]),
+            external: Some(vec![1.5, -2.0]),
+            ..output::tests::reports()[0].clone()
+        };
+        db.insert_tick(second, &[aapl]).unwrap();
+
+        let mut select = db
+            .connection
+            .prepare(
+                "SELECT run_time, period_start, symbol, last_price, pct_change, period_min, \
+                 period_max, sma, last_close_time, sma_series, external \
+                 FROM reports ORDER BY run_time, symbol",
+            )
+            .unwrap();
+        let rows: Vec<Record> = select
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                    row.get(8)?,
+                    row.get(9)?,
+                    row.get(10)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            (
+                "2021-11-05T16:00:00+00:00".to_string(),
+                "2021-11-01T00:00:00+00:00".to_string(),
+                "AAPL".to_string(),
+                Some(150.5),
+                Some(2.5),
+                Some(140.0),
+                Some(155.25),
+                Some(148.0),
+                "2021-11-05T00:00:00+00:00".to_string(),
+                None,
+                None,
+            )
+        );
+        assert_eq!(rows[1].2, "EURUSD=X");
+        assert_eq!(rows[1].0, rows[0].0);
+        assert_eq!(
+            rows[2],
+            (
+                "2021-11-05T16:00:30+00:00".to_string(),
+                "2021-11-01T00:00:00+00:00".to_string(),
+                "AAPL".to_string(),
+                Some(150.5),
+                None,
+                Some(140.0),
+                Some(155.25),
+                Some(148.0),
+                "2021-11-05T00:00:00+00:00".to_string(),
+                Some(r#"[{"timestamp":"2021-11-05T00:00:00Z","value":148.0}]"#.to_string()),
+                Some("[1.5,-2.0]".to_string()),
+            )
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//!
+//! Detecting a provider that keeps answering with the same old quotes.
+//!
+
+use chrono::prelude::*;
+use std::{collections::HashMap, sync::Mutex};
+
+///
+/// The time of the latest quote seen for each symbol, recorded by the tasks
+/// fetching them. A single instance is shared by all symbols and ticks.
+///
+#[derive(Debug, Default)]
+pub struct LatestQuotes {
+    latest: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl LatestQuotes {
+    ///
+    /// Remember `timestamp` for `symbol` unless a later one was seen before,
+    /// e.g. while fetching an older period to compare with.
+    ///
+    pub fn record(&self, symbol: &str, timestamp: DateTime<Utc>) {
+        let mut latest = self
+            .latest
+            .lock()
+            .expect("latest quotes are never poisoned");
+        let entry = latest.entry(symbol.to_string()).or_insert(timestamp);
+        *entry = (*entry).max(timestamp);
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, DateTime<Utc>> {
+        self.latest
+            .lock()
+            .expect("latest quotes are never poisoned")
+            .clone()
+    }
+}
+
+///
+/// Counts the consecutive ticks in which no symbol's latest quote advanced.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StaleTicks {
+    previous: HashMap<String, DateTime<Utc>>,
+    count: usize,
+}
+
+impl StaleTicks {
+    ///
+    /// Compare the latest quotes after a tick with those after the previous
+    /// one. A tick is stale unless a symbol got a later quote or a first one.
+    ///
+    /// # Returns
+    ///
+    /// The number of stale ticks in a row up to and including this one.
+    ///
+    pub fn observe(&mut self, latest: HashMap<String, DateTime<Utc>>) -> usize {
+        let advanced = latest.iter().any(|(symbol, time)| {
+            self.previous
+                .get(symbol)
+                .is_none_or(|previous| time > previous)
+        });
+        self.count = if advanced { 0 } else { self.count + 1 };
+        self.previous = latest;
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+
+    #[test]
+    fn test_LatestQuotes_record() {
+        let latest = LatestQuotes::default();
+        let (earlier, later) = (
+            Utc.ymd(2024, 6, 3).and_hms(0, 0, 0),
+            Utc.ymd(2024, 6, 4).and_hms(0, 0, 0),
+        );
+        latest.record("AAPL", later);
+        latest.record("AAPL", earlier);
+        latest.record("MSFT", earlier);
+        assert_eq!(
+            latest.snapshot(),
+            HashMap::from([("AAPL".to_string(), later), ("MSFT".to_string(), earlier)])
+        );
+    }
+
+    #[test]
+    fn test_StaleTicks_observe() {
+        let day = |d| Utc.ymd(2024, 6, d).and_hms(0, 0, 0);
+        let quotes = |aapl, msft| {
+            HashMap::from([
+                ("AAPL".to_string(), day(aapl)),
+                ("MSFT".to_string(), day(msft)),
+            ])
+        };
+        let mut stale = StaleTicks::default();
+        // the first quotes are news, then nothing moves
+        assert_eq!(stale.observe(quotes(3, 3)), 0);
+        assert_eq!(stale.observe(quotes(3, 3)), 1);
+        assert_eq!(stale.observe(quotes(3, 3)), 2);
+        // one symbol advancing is enough
+        assert_eq!(stale.observe(quotes(3, 4)), 0);
+        assert_eq!(stale.observe(quotes(3, 4)), 1);
+        assert_eq!(stale.observe(quotes(4, 4)), 0);
+        // so is a symbol added to the watchlist
+        let mut added = quotes(4, 4);
+        added.insert("UBER".to_string(), day(1));
+        assert_eq!(stale.observe(added.clone()), 0);
+        assert_eq!(stale.observe(added), 1);
+        // and nothing at all is stale too
+        assert_eq!(StaleTicks::default().observe(HashMap::new()), 1);
+    }
+}
@@ -0,0 +1,86 @@
+//!
+//! Running ticks in tasks of their own so a slow one overlaps with the next.
+//!
+
+use std::{future::Future, sync::Arc};
+use tokio::sync::{mpsc, Semaphore};
+
+///
+/// Runs up to `max` ticks at once and collects their results in the order
+/// they finish. Starting another tick waits for one of them to finish, so
+/// ticks that keep taking longer than the interval can't pile up.
+///
+pub struct TickPool<T> {
+    permits: Arc<Semaphore>,
+    sender: mpsc::UnboundedSender<T>,
+    receiver: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T: Send + 'static> TickPool<T> {
+    pub fn new(max: usize) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        TickPool {
+            permits: Arc::new(Semaphore::new(max)),
+            sender,
+            receiver,
+        }
+    }
+
+    ///
+    /// Wait until fewer than `max` ticks are running and spawn `tick`.
+    ///
+    pub async fn spawn(&self, tick: impl Future<Output = T> + Send + 'static) {
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .expect("the pool never closes its semaphore");
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let result = tick.await;
+            drop(permit);
+            // the pool holds a receiver for as long as it holds the sender
+            let _ = sender.send(result);
+        });
+    }
+
+    ///
+    /// The result of the next tick to finish.
+    ///
+    pub async fn next(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_ticks_overlap_up_to_max() {
+        let mut pool = TickPool::new(2);
+        let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        for i in 0..5 {
+            let (running, peak) = (Arc::clone(&running), Arc::clone(&peak));
+            // every tick is slower than starting the next one
+            pool.spawn(async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                i
+            })
+            .await;
+        }
+        let mut finished = vec![];
+        while finished.len() < 5 {
+            finished.extend(pool.next().await);
+        }
+        finished.sort_unstable();
+        assert_eq!(finished, [0, 1, 2, 3, 4]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}
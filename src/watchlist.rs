@@ -0,0 +1,123 @@
+//!
+//! The symbols to report on, optionally kept in a file that's re-read on
+//! every tick.
+//!
+
+use std::{
+    io::{self, Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+///
+/// The current symbols and the file they're loaded from, if any.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchlist {
+    path: Option<PathBuf>,
+    symbols: Vec<String>,
+}
+
+impl Watchlist {
+    ///
+    /// A watchlist that never changes.
+    ///
+    pub fn fixed(symbols: Vec<String>) -> Self {
+        Watchlist {
+            path: None,
+            symbols,
+        }
+    }
+
+    ///
+    /// Load the watchlist from a file, see `read_symbols`.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or doesn't contain a valid list.
+    ///
+    pub fn file(path: &Path) -> io::Result<Self> {
+        Ok(Watchlist {
+            path: Some(path.to_path_buf()),
+            symbols: read_symbols(path)?,
+        })
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    ///
+    /// Re-read the file, if any, and return the symbols. A file that can't be
+    /// read or is invalid, e.g. while it's being written, keeps the previous
+    /// symbols and is only logged.
+    ///
+    pub fn reload(&mut self) -> &[String] {
+        if let Some(path) = &self.path {
+            match read_symbols(path) {
+                Ok(symbols) => self.symbols = symbols,
+                Err(err) => eprintln!(
+                    "keeping the previous symbols, can't read '{}': {err}",
+                    path.display()
+                ),
+            }
+        }
+        &self.symbols
+    }
+}
+
+///
+/// Read symbols separated by commas or line breaks. Blank lines and lines
+/// starting with `#` are skipped.
+///
+/// # Errors
+///
+/// If the file can't be read, a symbol is invalid or there are no symbols.
+///
+fn read_symbols(path: &Path) -> io::Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)?;
+    let lines: Vec<_> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if lines.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "no symbols"));
+    }
+    crate::parse_symbols(&lines.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::sink::tests::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_Watchlist_reload() {
+        let dir = temp_dir("watchlist");
+        let path = dir.join("symbols.txt");
+        fs::write(&path, "# tech\naapl, msft\n\n").unwrap();
+        let mut watchlist = Watchlist::file(&path).unwrap();
+        assert_eq!(watchlist.symbols(), ["AAPL", "MSFT"]);
+
+        // the next tick picks up edits
+        fs::write(&path, "AAPL\nGOOG\nUBER\n").unwrap();
+        assert_eq!(watchlist.reload(), ["AAPL", "GOOG", "UBER"]);
+
+        // partial, invalid or missing files keep the previous symbols
+        fs::write(&path, "").unwrap();
+        assert_eq!(watchlist.reload(), ["AAPL", "GOOG", "UBER"]);
+        fs::write(&path, "AAPL,GO").unwrap();
+        assert_eq!(watchlist.reload(), ["AAPL", "GO"]);
+        fs::write(&path, "AAPL,GO OG").unwrap();
+        assert_eq!(watchlist.reload(), ["AAPL", "GO"]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watchlist.reload(), ["AAPL", "GO"]);
+        assert!(Watchlist::file(&path).is_err());
+
+        let mut fixed = Watchlist::fixed(vec!["AAPL".to_string()]);
+        assert_eq!(fixed.reload(), ["AAPL"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+//!
+//! Pushing each tick's rows to a webhook as JSON.
+//!
+
+use serde::Serialize;
+use std::time::Duration;
+
+///
+/// A webhook URL and the HTTP client to post to it with.
+///
+pub struct Webhook {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Webhook {
+    ///
+    /// Create a webhook where each request gives up after `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// If the HTTP client can't be set up.
+    ///
+    pub fn new(url: &str, timeout: Duration) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Webhook {
+            client,
+            url: url.to_string(),
+        })
+    }
+
+    ///
+    /// POST the rows as a JSON array.
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the webhook responds with an error status.
+    ///
+    pub async fn post<R: Serialize>(&self, rows: &[R]) -> reqwest::Result<()> {
+        self.client
+            .post(&self.url)
+            .json(rows)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+    use super::*;
+    use crate::output::tests::reports;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    ///
+    /// Accept a single HTTP request on a random local port, respond with
+    /// `status` and hand the request body to the returned thread's result.
+    ///
+    fn serve_once(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(stream, "HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_Webhook_post() {
+        let (url, server) = serve_once("200 OK");
+        let webhook = Webhook::new(&url, Duration::from_secs(5)).unwrap();
+        webhook.post(&reports()).await.unwrap();
+
+        let body: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(
+            r#"[
+                {
+                    "period_start": "2021-11-01T00:00:00Z",
+                    "symbol": "AAPL",
+                    "last_price": 150.5,
+                    "pct_change": 2.5,
+                    "period_min": 140.0,
+                    "period_max": 155.25,
+                    "sma": 148.0,
+                    "last_close_time": "2021-11-05T00:00:00Z"
+                },
+                {
+                    "period_start": "2021-11-01T00:00:00Z",
+                    "symbol": "EURUSD=X",
+                    "last_price": 1.1,
+                    "pct_change": -0.5,
+                    "period_min": 1.05,
+                    "period_max": 1.2,
+                    "sma": 1.125,
+                    "last_close_time": "2021-11-05T00:00:00Z"
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(body, expected);
+    }
+
+    #[tokio::test]
+    async fn test_Webhook_post_error_status() {
+        let (url, server) = serve_once("500 Internal Server Error");
+        let webhook = Webhook::new(&url, Duration::from_secs(5)).unwrap();
+        assert!(webhook.post(&reports()).await.is_err());
+        server.join().unwrap();
+    }
+}